@@ -0,0 +1,34 @@
+function urlBase64ToUint8Array(base64String) {
+    const padding = '='.repeat((4 - base64String.length % 4) % 4);
+    const base64 = (base64String + padding).replace(/-/g, '+').replace(/_/g, '/');
+    const raw = window.atob(base64);
+    return Uint8Array.from([...raw].map(char => char.charCodeAt(0)));
+}
+
+export function is_push_supported_js() {
+    return 'serviceWorker' in navigator && 'PushManager' in window;
+}
+
+export function subscribe_push_js(publicKey, onSubscribed, onError) {
+    navigator.serviceWorker.ready
+        .then(registration => registration.pushManager.subscribe({
+            userVisibleOnly: true,
+            applicationServerKey: urlBase64ToUint8Array(publicKey)
+        }))
+        .then(subscription => onSubscribed(JSON.stringify(subscription)))
+        .catch(e => onError(e.toString()));
+}
+
+export function unsubscribe_push_js(onUnsubscribed, onError) {
+    navigator.serviceWorker.ready
+        .then(registration => registration.pushManager.getSubscription())
+        .then(subscription => {
+            if (!subscription) {
+                onUnsubscribed("");
+                return;
+            }
+            const json = JSON.stringify(subscription);
+            return subscription.unsubscribe().then(() => onUnsubscribed(json));
+        })
+        .catch(e => onError(e.toString()));
+}
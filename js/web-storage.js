@@ -6,4 +6,9 @@ export const store_js = (key, value) => {
 export const get_stored_js = (key) => {
     const storage = window.localStorage;
     return localStorage.getItem(key);
-}
\ No newline at end of file
+}
+
+export const remove_stored_js = (key) => {
+    const storage = window.localStorage;
+    localStorage.removeItem(key);
+}
@@ -0,0 +1,146 @@
+use super::provider::WeatherProvider;
+use crate::common::*;
+use chrono::prelude::*;
+
+pub const DEFAULT_ENDPOINT: &'static str = "https://api.brightsky.dev/weather";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BrightSky {
+    endpoint: String,
+}
+
+impl BrightSky {
+    pub fn new(endpoint: String) -> BrightSky {
+        BrightSky { endpoint }
+    }
+}
+
+impl Default for BrightSky {
+    fn default() -> Self {
+        BrightSky::new(DEFAULT_ENDPOINT.to_owned())
+    }
+}
+
+impl WeatherProvider for BrightSky {
+    fn name(&self) -> &str {
+        "Bright Sky"
+    }
+
+    fn forecast_url(&self, lat: f32, lon: f32, start: DateTime<Utc>, end: DateTime<Utc>) -> String {
+        format!(
+            "{}?lat={}&lon={}&date={}&last_date={}",
+            self.endpoint,
+            lat,
+            lon,
+            start.to_rfc3339(),
+            end.to_rfc3339()
+        )
+        // TODO escape forbidden characters in a more robust way!
+        .replace("+", "%2b")
+    }
+
+    fn parse_forecast(&self, body: &str) -> Result<Forecast, BackendError> {
+        match serde_json::from_str::<brtsky::Response>(body) {
+            Ok(data) => Ok(to_forecast(&data)),
+            Err(e) => {
+                error!("Error parsing brightsky data: {}", e);
+                match serde_json::from_str::<BrightskyApiError>(body) {
+                    Ok(api_error) => Err(api_error.into()),
+                    Err(_) => Err(BackendError::ParseError(e.to_string())),
+                }
+            }
+        }
+    }
+}
+
+fn to_condition(condition: &brtsky::Condition) -> Option<Condition> {
+    match condition {
+        brtsky::Condition::Dry => Some(Condition::Dry),
+        brtsky::Condition::Fog => Some(Condition::Fog),
+        brtsky::Condition::Rain => Some(Condition::Rain),
+        brtsky::Condition::Sleet => Some(Condition::Sleet),
+        brtsky::Condition::Snow => Some(Condition::Snow),
+        brtsky::Condition::Hail => Some(Condition::Hail),
+        brtsky::Condition::Thunderstorm => Some(Condition::Thunderstorm),
+        brtsky::Condition::Null => None,
+    }
+}
+
+/// Bright Sky icons are plain strings, `"null"` and unknown names have no icon.
+fn to_icon(icon: &str) -> Option<Icon> {
+    match serde_json::from_value(serde_json::Value::String(icon.to_owned())) {
+        Ok(icon) => Some(icon),
+        Err(_) => {
+            if icon != "null" {
+                warn!("Unknown brightsky icon: {}", icon);
+            }
+            None
+        }
+    }
+}
+
+fn to_forecast(data: &brtsky::Response) -> Forecast {
+    let mut station = None;
+    let mut hours = Vec::new();
+
+    for data in data.weather_data_sets() {
+        let source = data.source();
+        station = Some(Station {
+            name: source.station_name.to_owned(),
+            lat: source.lat as f32,
+            lon: source.lon as f32,
+        });
+
+        let weather = data.weather_data();
+        hours.push(HourlyRecord {
+            timestamp: weather.timestamp.with_timezone(&Utc),
+            temperature: weather.temperature,
+            dew_point: weather.dew_point,
+            relative_humidity: weather.relative_humidity.map(|h| h as f32),
+            wind_speed: weather.wind_speed,
+            cloud_cover: weather.cloud_cover.map(|c| c as f32),
+            sunshine: weather.sunshine,
+            precipitation: weather.precipitation,
+            condition: weather.condition.as_ref().and_then(to_condition),
+            icon: weather.icon.as_deref().and_then(to_icon),
+        });
+    }
+
+    Forecast {
+        provider: None,
+        station,
+        hours,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_icons() {
+        let data = std::fs::read_to_string("test/test.json").unwrap();
+        let forecast = BrightSky::default().parse_forecast(&data).unwrap();
+
+        let icons: Vec<Option<Icon>> = forecast.hours.iter().take(13).map(|h| h.icon).collect();
+
+        assert_eq!(
+            icons,
+            vec![
+                Some(Icon::ClearDay),
+                Some(Icon::ClearNight),
+                Some(Icon::PartlyCloudyDay),
+                Some(Icon::PartlyCloudyNight),
+                Some(Icon::Cloudy),
+                Some(Icon::Fog),
+                Some(Icon::Wind),
+                Some(Icon::Rain),
+                Some(Icon::Sleet),
+                Some(Icon::Snow),
+                Some(Icon::Hail),
+                Some(Icon::Thunderstorm),
+                None,
+            ]
+        );
+    }
+}
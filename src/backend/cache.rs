@@ -0,0 +1,183 @@
+use crate::common::*;
+use chrono::prelude::*;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// coordinates are rounded to 1/100 degree (roughly 1 km) so that nearby users share cache entries
+const COORDINATE_PRECISION: f32 = 100.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    lat: i32,
+    lon: i32,
+    start: i64,
+    end: i64,
+}
+
+impl CacheKey {
+    pub fn new<Tz: TimeZone>(
+        lat: f32,
+        lon: f32,
+        start: &DateTime<Tz>,
+        end: &DateTime<Tz>,
+    ) -> CacheKey {
+        CacheKey {
+            lat: (lat * COORDINATE_PRECISION).round() as i32,
+            lon: (lon * COORDINATE_PRECISION).round() as i32,
+            start: start.timestamp() / 3600,
+            end: end.timestamp() / 3600,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    forecast: Forecast,
+    inserted: Instant,
+}
+
+#[derive(Debug)]
+pub struct ForecastCache {
+    ttl: Duration,
+    capacity: usize,
+    entries: Mutex<HashMap<CacheKey, CacheEntry>>,
+}
+
+impl ForecastCache {
+    pub fn new(ttl: Duration, capacity: usize) -> ForecastCache {
+        ForecastCache {
+            ttl,
+            capacity,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn get(&self, key: &CacheKey) -> Option<Forecast> {
+        let mut entries = self.entries.lock().expect("cache mutex poisoned");
+        match entries.get(key) {
+            Some(entry) if entry.inserted.elapsed() < self.ttl => Some(entry.forecast.clone()),
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub fn insert(&self, key: CacheKey, forecast: Forecast) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut entries = self.entries.lock().expect("cache mutex poisoned");
+
+        if entries.len() >= self.capacity && !entries.contains_key(&key) {
+            let ttl = self.ttl;
+            entries.retain(|_, entry| entry.inserted.elapsed() < ttl);
+        }
+
+        if entries.len() >= self.capacity && !entries.contains_key(&key) {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.inserted)
+                .map(|(key, _)| *key);
+            if let Some(oldest) = oldest {
+                entries.remove(&oldest);
+            }
+        }
+
+        entries.insert(
+            key,
+            CacheEntry {
+                forecast,
+                inserted: Instant::now(),
+            },
+        );
+    }
+
+    /// Returns the cached forecast for the key or runs `fetch` and caches its result if it succeeds.
+    pub fn get_or_fetch<E, F>(&self, key: CacheKey, fetch: F) -> Result<Forecast, E>
+    where
+        F: FnOnce() -> Result<Forecast, E>,
+    {
+        if let Some(forecast) = self.get(&key) {
+            debug!("Serving forecast from cache.");
+            return Ok(forecast);
+        }
+
+        let forecast = fetch()?;
+        self.insert(key, forecast.clone());
+        Ok(forecast)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().expect("cache mutex poisoned").len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn forecast(name: &str) -> Forecast {
+        Forecast {
+            provider: None,
+            station: Some(Station {
+                name: name.to_owned(),
+                lat: 0.0,
+                lon: 0.0,
+            }),
+            hours: Vec::new(),
+        }
+    }
+
+    fn key(lat: f32, lon: f32) -> CacheKey {
+        let start = Utc.ymd(2020, 10, 21).and_hms(14, 35, 0);
+        let end = Utc.ymd(2020, 10, 24).and_hms(12, 0, 0);
+        CacheKey::new(lat, lon, &start, &end)
+    }
+
+    #[test]
+    fn test_nearby_coordinates_share_entry() {
+        let cache = ForecastCache::new(Duration::from_secs(600), 10);
+        cache.insert(key(49.881, 10.921), forecast("BAMBERG"));
+
+        assert_eq!(cache.get(&key(49.8812, 10.9208)), Some(forecast("BAMBERG")));
+        assert_eq!(cache.get(&key(49.9, 10.92)), None);
+    }
+
+    #[test]
+    fn test_expired_entries_are_not_served() {
+        let cache = ForecastCache::new(Duration::from_secs(0), 10);
+        cache.insert(key(49.88, 10.92), forecast("BAMBERG"));
+
+        assert_eq!(cache.get(&key(49.88, 10.92)), None);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_size_bound() {
+        let cache = ForecastCache::new(Duration::from_secs(600), 2);
+        cache.insert(key(1.0, 1.0), forecast("one"));
+        cache.insert(key(2.0, 2.0), forecast("two"));
+        cache.insert(key(3.0, 3.0), forecast("three"));
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&key(1.0, 1.0)), None);
+        assert_eq!(cache.get(&key(3.0, 3.0)), Some(forecast("three")));
+    }
+
+    #[test]
+    fn test_failed_fetch_is_not_cached() {
+        let cache = ForecastCache::new(Duration::from_secs(600), 10);
+        let result: Result<Forecast, ()> = cache.get_or_fetch(key(1.0, 1.0), || Err(()));
+
+        assert!(result.is_err());
+        assert!(cache.is_empty());
+    }
+}
@@ -0,0 +1,112 @@
+use crate::common::*;
+
+// hoar frost is still possible at these air temperatures because the ground cools below the air
+const MAX_AIR_TEMPERATURE: f32 = 4.0;
+// without enough moisture close to saturation nothing deposits on the ground
+const MAX_DEW_POINT_SPREAD: f32 = 4.0;
+// clouds reflect the radiation that would otherwise leave the ground
+const MAX_CLOUD_COVER: f32 = 40.0;
+// wind mixes the warmer air above into the cold layer at the ground
+const MAX_WIND_SPEED: f32 = 10.0;
+
+/// Dew point in °C from temperature in °C and relative humidity in %, using the Magnus formula.
+pub fn dew_point(temperature: f32, relative_humidity: f32) -> f32 {
+    let gamma = (relative_humidity.max(1.0) / 100.0).ln() + magnus_water(temperature);
+    243.12 * gamma / (17.62 - gamma)
+}
+
+/// Frost point in °C (the temperature at which moisture deposits as ice) from the dew point in °C.
+pub fn frost_point(dew_point: f32) -> f32 {
+    let gamma = magnus_water(dew_point);
+    272.62 * gamma / (22.46 - gamma)
+}
+
+fn magnus_water(temperature: f32) -> f32 {
+    17.62 * temperature / (243.12 + temperature)
+}
+
+/// Checks if the conditions of a forecast hour are likely to cause hoar frost on plants.
+pub fn assess(hour: &HourlyRecord) -> Option<FrostRisk> {
+    let temperature = hour.temperature?;
+    let dew_point = hour.dew_point.or_else(|| {
+        hour.relative_humidity
+            .map(|humidity| dew_point(temperature, humidity))
+    })?;
+
+    let frost_point = frost_point(dew_point);
+    let dew_point_spread = temperature - dew_point;
+
+    // missing values are treated as favourable for frost, better safe than sorry
+    let clear_sky = hour.cloud_cover.map_or(true, |c| c <= MAX_CLOUD_COVER);
+    let calm = hour.wind_speed.map_or(true, |w| w <= MAX_WIND_SPEED);
+
+    if temperature <= MAX_AIR_TEMPERATURE
+        && frost_point <= 0.0
+        && dew_point_spread <= MAX_DEW_POINT_SPREAD
+        && clear_sky
+        && calm
+    {
+        Some(FrostRisk {
+            frost_point,
+            dew_point_spread,
+            relative_humidity: hour.relative_humidity,
+            cloud_cover: hour.cloud_cover,
+            wind_speed: hour.wind_speed,
+        })
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::prelude::*;
+
+    fn hour(temperature: f32, dew_point: f32, cloud_cover: f32, wind_speed: f32) -> HourlyRecord {
+        HourlyRecord {
+            timestamp: Utc.ymd(2020, 10, 21).and_hms(4, 0, 0),
+            temperature: Some(temperature),
+            dew_point: Some(dew_point),
+            relative_humidity: None,
+            wind_speed: Some(wind_speed),
+            cloud_cover: Some(cloud_cover),
+            sunshine: None,
+            precipitation: None,
+            condition: None,
+            icon: None,
+        }
+    }
+
+    #[test]
+    fn test_frost_point() {
+        assert!((frost_point(0.0) - 0.0).abs() < 0.01);
+        assert!((frost_point(-5.0) - -4.4).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_dew_point() {
+        assert!((dew_point(10.0, 100.0) - 10.0).abs() < 0.01);
+        assert!((dew_point(10.0, 50.0) - 0.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_clear_calm_night_above_zero() {
+        let risk = assess(&hour(2.0, -0.5, 5.0, 3.0)).unwrap();
+
+        assert!(risk.frost_point < 0.0);
+        assert_eq!(risk.dew_point_spread, 2.5);
+    }
+
+    #[test]
+    fn test_no_frost_risk() {
+        // cloudy
+        assert_eq!(assess(&hour(2.0, -0.5, 90.0, 3.0)), None);
+        // windy
+        assert_eq!(assess(&hour(2.0, -0.5, 5.0, 25.0)), None);
+        // too dry
+        assert_eq!(assess(&hour(2.0, -8.0, 5.0, 3.0)), None);
+        // too warm
+        assert_eq!(assess(&hour(6.0, 2.5, 5.0, 3.0)), None);
+    }
+}
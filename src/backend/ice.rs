@@ -0,0 +1,149 @@
+use super::time_zone::to_local_time;
+use crate::common::*;
+use chrono::Duration;
+use chrono_tz::Tz;
+
+// road and path surfaces can freeze before the air at 2 m does
+const NEAR_FREEZING: f32 = 1.0;
+// surfaces are considered wet for this many hours after precipitation
+const WET_HOURS: u32 = 3;
+
+/// Finds periods in which snow, freezing rain or refreezing wet surfaces make roads and paths
+/// slippery.
+pub fn detect_ice_hazards(hours: &[HourlyRecord], tz: &Tz) -> Vec<IceHazard> {
+    let mut hazards = Vec::new();
+    let mut current_hazard: Option<IceHazard> = None;
+    let mut hours_since_precipitation: Option<u32> = None;
+
+    for hour in hours {
+        let precipitation = hour.precipitation.unwrap_or(0.0);
+        let precipitating = precipitation > 0.0
+            || hour.condition.map_or(false, |c| {
+                c != Condition::Dry && c != Condition::Fog && c != Condition::Thunderstorm
+            });
+
+        hours_since_precipitation = if precipitating {
+            Some(0)
+        } else {
+            hours_since_precipitation.map(|h| h + 1)
+        };
+
+        let kind = hour.temperature.and_then(|temp| {
+            classify(
+                hour,
+                temp,
+                hours_since_precipitation,
+                current_hazard.is_some(),
+            )
+        });
+
+        match (kind, current_hazard.as_mut()) {
+            (Some(kind), Some(hazard)) => {
+                let temp = hour
+                    .temperature
+                    .expect("classified hours have a temperature");
+                if kind > hazard.kind {
+                    hazard.kind = kind;
+                }
+                if temp < hazard.min_temp {
+                    hazard.min_temp = temp;
+                }
+                hazard.precipitation += precipitation;
+                hazard.end = to_local_time(&hour.timestamp, tz) + Duration::hours(1);
+            }
+            (Some(kind), None) => {
+                let start = to_local_time(&hour.timestamp, tz);
+                current_hazard = Some(IceHazard {
+                    kind,
+                    start,
+                    end: start + Duration::hours(1),
+                    min_temp: hour
+                        .temperature
+                        .expect("classified hours have a temperature"),
+                    precipitation,
+                });
+            }
+            (None, Some(_)) => hazards.extend(current_hazard.take()),
+            (None, None) => (),
+        }
+    }
+
+    hazards.extend(current_hazard);
+
+    hazards
+}
+
+fn classify(
+    hour: &HourlyRecord,
+    temp: f32,
+    hours_since_precipitation: Option<u32>,
+    already_icy: bool,
+) -> Option<IceHazardKind> {
+    let precipitating = hours_since_precipitation == Some(0);
+    let wet = hours_since_precipitation.map_or(false, |h| h <= WET_HOURS);
+
+    match hour.condition {
+        Some(Condition::FreezingRain) => Some(IceHazardKind::FreezingRain),
+        Some(Condition::Rain) if temp <= 0.0 => Some(IceHazardKind::FreezingRain),
+        Some(Condition::Fog) if temp <= 0.0 => Some(IceHazardKind::BlackIce),
+        Some(c) if c.is_frozen() && temp <= NEAR_FREEZING => Some(IceHazardKind::Snow),
+        None if precipitating && temp <= NEAR_FREEZING => Some(IceHazardKind::Snow),
+        _ if wet && !precipitating && temp <= NEAR_FREEZING => Some(IceHazardKind::BlackIce),
+        // once frozen, surfaces stay icy until it thaws
+        _ if already_icy && temp <= 0.0 => Some(IceHazardKind::BlackIce),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::backend::brightsky::BrightSky;
+    use crate::backend::provider::WeatherProvider;
+    use chrono::prelude::*;
+
+    #[test]
+    fn test_rain_followed_by_frost() {
+        let data = std::fs::read_to_string("test/black_ice.json").unwrap();
+        let data = BrightSky::default().parse_forecast(&data).unwrap();
+        let hazards = detect_ice_hazards(&data.hours, &chrono_tz::UTC);
+
+        assert_eq!(hazards.len(), 1);
+
+        let hazard = &hazards[0];
+        assert_eq!(hazard.kind, IceHazardKind::BlackIce);
+        assert_eq!(hazard.min_temp, -1.8);
+        assert!((hazard.precipitation - 0.5).abs() < 0.001);
+        assert_eq!(hazard.start.to_rfc3339(), "2020-12-02T03:00:00+00:00");
+        assert_eq!(hazard.end.to_rfc3339(), "2020-12-02T09:00:00+00:00");
+    }
+
+    #[test]
+    fn test_freezing_rain() {
+        let hour = |h: u32, temperature: f32, condition: Condition| HourlyRecord {
+            timestamp: Utc.ymd(2020, 12, 2).and_hms(h, 0, 0),
+            temperature: Some(temperature),
+            dew_point: None,
+            relative_humidity: None,
+            wind_speed: None,
+            cloud_cover: None,
+            sunshine: None,
+            precipitation: Some(0.6),
+            condition: Some(condition),
+            icon: None,
+        };
+        let hours = vec![
+            hour(0, 1.2, Condition::Rain),
+            hour(1, -0.3, Condition::Rain),
+            hour(2, -0.8, Condition::FreezingRain),
+            hour(3, 2.4, Condition::Rain),
+        ];
+
+        let hazards = detect_ice_hazards(&hours, &chrono_tz::UTC);
+
+        assert_eq!(hazards.len(), 1);
+        assert_eq!(hazards[0].kind, IceHazardKind::FreezingRain);
+        assert_eq!(hazards[0].start.to_rfc3339(), "2020-12-02T01:00:00+00:00");
+        assert_eq!(hazards[0].end.to_rfc3339(), "2020-12-02T03:00:00+00:00");
+    }
+}
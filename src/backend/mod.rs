@@ -1,82 +1,230 @@
+use self::phases::PhaseAccumulator;
+use self::summary::NightAccumulator;
+use self::time_zone::to_local_time;
 use super::common::*;
 use chrono::prelude::*;
+use chrono::Duration;
+use chrono_tz::Tz;
 
-pub fn accumulate_cold_phases(
-    warning_threshold: f32,
-    danger_threshold: f32,
-    data: &brtsky::Response,
-) -> BackendResponse {
-    let mut phases: Vec<ColdPhase> = Vec::new();
-
-    let mut current_phase: Option<ColdPhase> = None;
-
-    let mut location = None;
-
-    for data in data.weather_data_sets() {
-        location = Some(data.source().station_name.to_owned());
-
-        if let Some(temp) = data.weather_data().temperature {
-            if temp > warning_threshold {
-                // end current phase if there is on
-                if let Some(phase) = current_phase.as_mut() {
-                    phases.push(phase.clone());
-                    current_phase = None;
-                }
-            } else if let Some(phase) = current_phase.as_mut() {
-                // update current phase if there is one
-                if temp < phase.min_temp {
-                    phase.min_temp = temp;
-                }
-                if temp <= danger_threshold {
-                    phase.record_type = RecordType::Danger;
-                }
-                phase.end = data.weather_data().timestamp.with_timezone(&Local)
-                    + chrono::Duration::hours(1);
-            } else {
-                // start new phase
-                let phase = ColdPhase {
-                    min_temp: temp,
-                    start: data.weather_data().timestamp.with_timezone(&Local),
-                    end: data.weather_data().timestamp.with_timezone(&Local)
-                        + chrono::Duration::hours(1),
-                    record_type: if temp <= danger_threshold {
-                        RecordType::Danger
-                    } else {
-                        RecordType::Warning
-                    },
-                    warning_threshold,
-                    danger_threshold,
-                };
-                current_phase = Some(phase);
-            }
+pub mod brightsky;
+pub mod cache;
+pub mod frost_risk;
+pub mod ice;
+pub mod open_meteo;
+pub mod phases;
+pub mod provider;
+pub mod summary;
+pub mod time_zone;
+pub mod wind_chill;
+pub mod window;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnalysisOptions {
+    pub warning_threshold: f32,
+    pub danger_threshold: f32,
+    pub level: TemperatureLevel,
+    /// Also treat hours as cold when the wind chill drops below the thresholds.
+    pub wind_chill: bool,
+    /// How far beyond the warning threshold the temperature has to get back to end a phase.
+    pub hysteresis: f32,
+    /// Phases shorter than this are dropped.
+    pub min_duration: Duration,
+    /// Phases separated by gaps no longer than this are merged.
+    pub max_gap: Duration,
+    /// Upper warning and danger thresholds, heat phases are only detected if these are set.
+    pub heat_thresholds: Option<Thresholds>,
+    /// Name of the plant preset the thresholds were taken from.
+    pub preset: Option<String>,
+}
+
+impl AnalysisOptions {
+    pub fn new(warning_threshold: f32, danger_threshold: f32) -> AnalysisOptions {
+        AnalysisOptions {
+            warning_threshold,
+            danger_threshold,
+            level: TemperatureLevel::default(),
+            wind_chill: false,
+            hysteresis: 0.0,
+            min_duration: Duration::zero(),
+            max_gap: Duration::zero(),
+            heat_thresholds: None,
+            preset: None,
+        }
+    }
+
+    pub fn from_preset(preset: &PlantPreset) -> AnalysisOptions {
+        let (warning_threshold, danger_threshold) = preset.thresholds;
+        AnalysisOptions {
+            level: preset.level,
+            preset: Some(preset.name.to_owned()),
+            ..AnalysisOptions::new(warning_threshold, danger_threshold)
         }
     }
+}
+
+pub fn accumulate_cold_phases(
+    options: &AnalysisOptions,
+    tz: Tz,
+    forecast: &Forecast,
+) -> BackendResponse {
+    let warning_threshold = options.warning_threshold;
+    let danger_threshold = options.danger_threshold;
+    let exit_threshold = warning_threshold + options.hysteresis.max(0.0);
+
+    let mut phases = PhaseAccumulator::new();
+    let mut nights = NightAccumulator::new();
+
+    let location = forecast.station.as_ref().map(|s| s.name.to_owned());
+
+    for data in &forecast.hours {
+        let temperature = match options.level {
+            TemperatureLevel::Air => data.temperature,
+            TemperatureLevel::Ground => ground_temperature(data),
+        };
+
+        let apparent_temperature = if options.wind_chill {
+            data.temperature
+                .zip(data.wind_speed)
+                .map(|(temp, wind_speed)| wind_chill::apparent_temperature(temp, wind_speed))
+        } else {
+            None
+        };
+
+        // whichever metric is lower decides
+        let (temp, trigger) = match (temperature, apparent_temperature) {
+            (Some(temp), Some(apparent)) if apparent < temp => (apparent, Trigger::WindChill),
+            (Some(temp), _) => (temp, Trigger::Temperature),
+            (None, Some(apparent)) => (apparent, Trigger::WindChill),
+            (None, None) => continue,
+        };
+
+        let frost_risk = frost_risk::assess(data);
+
+        let record_type = if temp <= danger_threshold {
+            RecordType::Danger
+        } else if frost_risk.is_some() {
+            RecordType::GroundFrost
+        } else {
+            RecordType::Warning
+        };
+
+        let is_cold = temp <= warning_threshold || frost_risk.is_some();
+        let stays_cold = temp <= exit_threshold || frost_risk.is_some();
+
+        let start = to_local_time(&data.timestamp, &tz);
+
+        nights.push(start, temp, is_cold.then(|| record_type.clone()));
+
+        let hour = ColdPhase {
+            min_temp: temp,
+            start,
+            end: start + Duration::hours(1),
+            record_type,
+            warning_threshold,
+            danger_threshold,
+            level: options.level,
+            trigger,
+            frost_risk,
+            min_temp_at: Some(start),
+            samples: vec![to_sample(data, start, temperature, apparent_temperature)],
+            preset: options.preset.clone(),
+        };
 
-    if let Some(phase) = current_phase.as_mut() {
-        phases.push(phase.clone());
+        phases.push(hour, is_cold, stays_cold);
     }
 
+    let heat_phases = options
+        .heat_thresholds
+        .map(|thresholds| accumulate_heat_phases(options, thresholds, &tz, forecast))
+        .unwrap_or_default();
+
     BackendResponse {
         location,
-        cold_phases: phases,
+        provider: forecast.provider.clone(),
+        time_zone: Some(tz.name().to_owned()),
+        cold_phases: phases.finish(options.max_gap, options.min_duration),
+        heat_phases,
+        nights: nights.finish(),
+        ice_hazards: ice::detect_ice_hazards(&forecast.hours, &tz),
+    }
+}
+
+fn accumulate_heat_phases(
+    options: &AnalysisOptions,
+    (warning_threshold, danger_threshold): Thresholds,
+    tz: &Tz,
+    forecast: &Forecast,
+) -> Vec<HeatPhase> {
+    let exit_threshold = warning_threshold - options.hysteresis.max(0.0);
+
+    let mut phases = PhaseAccumulator::new();
+
+    for data in &forecast.hours {
+        let temp = match data.temperature {
+            Some(temp) => temp,
+            None => continue,
+        };
+
+        let record_type = if temp >= danger_threshold {
+            RecordType::Danger
+        } else {
+            RecordType::Warning
+        };
+
+        let start = to_local_time(&data.timestamp, tz);
+        let hour = HeatPhase {
+            max_temp: temp,
+            start,
+            end: start + Duration::hours(1),
+            record_type,
+            warning_threshold,
+            danger_threshold,
+            max_temp_at: Some(start),
+            samples: vec![to_sample(data, start, Some(temp), None)],
+        };
+
+        phases.push(hour, temp >= warning_threshold, temp >= exit_threshold);
+    }
+
+    phases.finish(options.max_gap, options.min_duration)
+}
+
+fn to_sample(
+    data: &HourlyRecord,
+    timestamp: DateTime<FixedOffset>,
+    temperature: Option<f32>,
+    apparent_temperature: Option<f32>,
+) -> PhaseSample {
+    PhaseSample {
+        timestamp,
+        temperature,
+        apparent_temperature,
+        dew_point: data.dew_point,
+        relative_humidity: data.relative_humidity,
+        wind_speed: data.wind_speed,
+        cloud_cover: data.cloud_cover,
     }
 }
 
 #[cfg(test)]
 mod test {
+    use super::brightsky::BrightSky;
+    use super::provider::WeatherProvider;
     use super::*;
 
     #[test]
     fn test() {
-        let data = std::fs::read("test/test.json").unwrap();
-        let data: brtsky::Response = serde_json::from_slice(&data).unwrap();
-        let cold_phases = accumulate_cold_phases(10.0, 7.0, &data).cold_phases;
+        let data = std::fs::read_to_string("test/test.json").unwrap();
+        let data = BrightSky::default().parse_forecast(&data).unwrap();
+        let options = AnalysisOptions::new(10.0, 7.0);
+        let cold_phases =
+            accumulate_cold_phases(&options, chrono_tz::Europe::Berlin, &data).cold_phases;
 
         assert_eq!(cold_phases.len(), 1);
 
         let json = serde_json::to_string(&cold_phases).unwrap();
 
-        let expected_json = r#"[{"min_temp":6.7,"start":"2020-04-21T04:00:00+02:00","end":"2020-04-21T09:00:00+02:00","record_type":"Danger","warning_threshold":10.0,"danger_threshold":7.0}]"#;
+        let expected_json = r#"[{"min_temp":6.7,"start":"2020-04-21T04:00:00+02:00","end":"2020-04-21T09:00:00+02:00","record_type":"Danger","warning_threshold":10.0,"danger_threshold":7.0,"level":"Air","trigger":"Temperature","frost_risk":null,"min_temp_at":"2020-04-21T07:00:00+02:00","samples":[{"timestamp":"2020-04-21T04:00:00+02:00","temperature":9.5,"apparent_temperature":null,"dew_point":-2.5,"relative_humidity":43.0,"wind_speed":12.6,"cloud_cover":0.0},{"timestamp":"2020-04-21T05:00:00+02:00","temperature":7.5,"apparent_temperature":null,"dew_point":-1.9,"relative_humidity":51.0,"wind_speed":10.8,"cloud_cover":0.0},{"timestamp":"2020-04-21T06:00:00+02:00","temperature":7.0,"apparent_temperature":null,"dew_point":-1.4,"relative_humidity":55.0,"wind_speed":8.6,"cloud_cover":0.0},{"timestamp":"2020-04-21T07:00:00+02:00","temperature":6.7,"apparent_temperature":null,"dew_point":-0.9,"relative_humidity":58.0,"wind_speed":13.3,"cloud_cover":0.0},{"timestamp":"2020-04-21T08:00:00+02:00","temperature":8.3,"apparent_temperature":null,"dew_point":-0.8,"relative_humidity":53.0,"wind_speed":13.0,"cloud_cover":0.0}],"preset":null}]"#;
 
         assert_eq!(&json, expected_json);
 
@@ -84,4 +232,190 @@ mod test {
 
         assert_eq!(cold_phases, roundtrip);
     }
+
+    #[test]
+    fn test_ground_frost_above_thresholds() {
+        let data = std::fs::read_to_string("test/ground_frost.json").unwrap();
+        let data = BrightSky::default().parse_forecast(&data).unwrap();
+        let options = AnalysisOptions::new(0.0, -2.0);
+        let cold_phases =
+            accumulate_cold_phases(&options, chrono_tz::Europe::Berlin, &data).cold_phases;
+
+        assert_eq!(cold_phases.len(), 1);
+
+        let phase = &cold_phases[0];
+        assert_eq!(phase.record_type, RecordType::GroundFrost);
+        assert_eq!(phase.min_temp, 1.6);
+        assert_eq!(phase.start.to_rfc3339(), "2020-10-21T03:00:00+02:00");
+        assert_eq!(phase.end.to_rfc3339(), "2020-10-21T07:00:00+02:00");
+        assert!(phase.frost_risk.as_ref().unwrap().frost_point < 0.0);
+    }
+
+    #[test]
+    fn test_ground_level() {
+        let data = std::fs::read_to_string("test/ground_frost.json").unwrap();
+        let data = BrightSky::default().parse_forecast(&data).unwrap();
+        let options = AnalysisOptions {
+            level: TemperatureLevel::Ground,
+            ..AnalysisOptions::new(0.0, -2.0)
+        };
+        let cold_phases =
+            accumulate_cold_phases(&options, chrono_tz::Europe::Berlin, &data).cold_phases;
+
+        assert_eq!(cold_phases.len(), 1);
+
+        let phase = &cold_phases[0];
+        assert_eq!(phase.level, TemperatureLevel::Ground);
+        assert_eq!(phase.record_type, RecordType::Danger);
+        assert!(phase.min_temp < -2.0);
+        assert_eq!(phase.start.to_rfc3339(), "2020-10-21T03:00:00+02:00");
+        assert_eq!(phase.end.to_rfc3339(), "2020-10-21T08:00:00+02:00");
+    }
+
+    #[test]
+    fn test_wind_chill() {
+        let data = std::fs::read_to_string("test/test.json").unwrap();
+        let data = BrightSky::default().parse_forecast(&data).unwrap();
+
+        let options = AnalysisOptions::new(10.0, 5.0);
+        let cold_phases =
+            accumulate_cold_phases(&options, chrono_tz::Europe::Berlin, &data).cold_phases;
+        assert_eq!(cold_phases[0].record_type, RecordType::Warning);
+        assert_eq!(cold_phases[0].trigger, Trigger::Temperature);
+
+        let options = AnalysisOptions {
+            wind_chill: true,
+            ..AnalysisOptions::new(10.0, 5.0)
+        };
+        let cold_phases =
+            accumulate_cold_phases(&options, chrono_tz::Europe::Berlin, &data).cold_phases;
+
+        assert_eq!(cold_phases.len(), 1);
+
+        let phase = &cold_phases[0];
+        assert_eq!(phase.record_type, RecordType::Danger);
+        assert_eq!(phase.trigger, Trigger::WindChill);
+        assert!((phase.min_temp - 4.1).abs() < 0.1);
+        assert_eq!(phase.start.to_rfc3339(), "2020-04-21T04:00:00+02:00");
+        assert_eq!(phase.end.to_rfc3339(), "2020-04-21T09:00:00+02:00");
+    }
+
+    fn hovering_phases(options: &AnalysisOptions) -> Vec<(String, String)> {
+        let data = std::fs::read_to_string("test/hovering.json").unwrap();
+        let data = BrightSky::default().parse_forecast(&data).unwrap();
+        accumulate_cold_phases(options, chrono_tz::UTC, &data)
+            .cold_phases
+            .iter()
+            .map(|p| {
+                (
+                    p.start.format("%H:%M").to_string(),
+                    p.end.format("%H:%M").to_string(),
+                )
+            })
+            .collect()
+    }
+
+    fn span(start: &str, end: &str) -> (String, String) {
+        (start.to_owned(), end.to_owned())
+    }
+
+    #[test]
+    fn test_fragmented_without_rules() {
+        let options = AnalysisOptions::new(5.0, 0.0);
+
+        assert_eq!(
+            hovering_phases(&options),
+            vec![
+                span("20:00", "21:00"),
+                span("22:00", "00:00"),
+                span("02:00", "04:00"),
+                span("05:00", "06:00")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_hysteresis() {
+        let options = AnalysisOptions {
+            hysteresis: 0.5,
+            ..AnalysisOptions::new(5.0, 0.0)
+        };
+
+        assert_eq!(
+            hovering_phases(&options),
+            vec![span("20:00", "04:00"), span("05:00", "06:00")]
+        );
+    }
+
+    #[test]
+    fn test_merge_short_gaps() {
+        let options = AnalysisOptions {
+            hysteresis: 0.5,
+            max_gap: Duration::hours(1),
+            ..AnalysisOptions::new(5.0, 0.0)
+        };
+
+        assert_eq!(hovering_phases(&options), vec![span("20:00", "06:00")]);
+
+        let options = AnalysisOptions {
+            max_gap: Duration::hours(2),
+            ..AnalysisOptions::new(5.0, 0.0)
+        };
+
+        assert_eq!(hovering_phases(&options), vec![span("20:00", "06:00")]);
+    }
+
+    #[test]
+    fn test_min_duration() {
+        let options = AnalysisOptions {
+            min_duration: Duration::hours(2),
+            ..AnalysisOptions::new(5.0, 0.0)
+        };
+
+        assert_eq!(
+            hovering_phases(&options),
+            vec![span("22:00", "00:00"), span("02:00", "04:00")]
+        );
+    }
+
+    #[test]
+    fn test_heat_phases() {
+        let data = std::fs::read_to_string("test/test.json").unwrap();
+        let data = BrightSky::default().parse_forecast(&data).unwrap();
+
+        let options = AnalysisOptions::new(10.0, 7.0);
+        let heat_phases =
+            accumulate_cold_phases(&options, chrono_tz::Europe::Berlin, &data).heat_phases;
+        assert!(heat_phases.is_empty());
+
+        let options = AnalysisOptions {
+            heat_thresholds: Some((17.0, 17.6)),
+            ..AnalysisOptions::new(10.0, 7.0)
+        };
+        let response = accumulate_cold_phases(&options, chrono_tz::Europe::Berlin, &data);
+
+        assert_eq!(response.cold_phases.len(), 1);
+        assert_eq!(response.heat_phases.len(), 1);
+
+        let phase = &response.heat_phases[0];
+        assert_eq!(phase.max_temp, 17.7);
+        assert_eq!(phase.record_type, RecordType::Danger);
+        assert_eq!(phase.start.to_rfc3339(), "2020-04-21T15:00:00+02:00");
+        assert_eq!(phase.end.to_rfc3339(), "2020-04-21T20:00:00+02:00");
+    }
+
+    #[test]
+    fn test_preset() {
+        let data = std::fs::read_to_string("test/test.json").unwrap();
+        let data = BrightSky::default().parse_forecast(&data).unwrap();
+        let options = AnalysisOptions::from_preset(plant_preset("tomatoes").unwrap());
+        let cold_phases =
+            accumulate_cold_phases(&options, chrono_tz::Europe::Berlin, &data).cold_phases;
+
+        assert_eq!(cold_phases.len(), 1);
+        assert_eq!(cold_phases[0].warning_threshold, 10.0);
+        assert_eq!(cold_phases[0].danger_threshold, 3.0);
+        assert_eq!(cold_phases[0].record_type, RecordType::Warning);
+        assert_eq!(cold_phases[0].preset.as_deref(), Some("tomatoes"));
+    }
 }
@@ -0,0 +1,230 @@
+use super::provider::WeatherProvider;
+use crate::common::*;
+use chrono::prelude::*;
+
+pub const DEFAULT_ENDPOINT: &'static str = "https://api.open-meteo.com/v1/forecast";
+
+const HOURLY_VARIABLES: &'static str =
+    "temperature_2m,dew_point_2m,relative_humidity_2m,wind_speed_10m,\
+    cloud_cover,sunshine_duration,precipitation,weather_code,is_day";
+const TIME_FORMAT: &'static str = "%Y-%m-%dT%H:%M";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenMeteo {
+    endpoint: String,
+}
+
+impl OpenMeteo {
+    pub fn new(endpoint: String) -> OpenMeteo {
+        OpenMeteo { endpoint }
+    }
+}
+
+impl Default for OpenMeteo {
+    fn default() -> Self {
+        OpenMeteo::new(DEFAULT_ENDPOINT.to_owned())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenMeteoResponse {
+    latitude: f32,
+    longitude: f32,
+    hourly: Hourly,
+}
+
+#[derive(Debug, Deserialize)]
+struct Hourly {
+    time: Vec<String>,
+    temperature_2m: Vec<Option<f32>>,
+    #[serde(default)]
+    dew_point_2m: Vec<Option<f32>>,
+    #[serde(default)]
+    relative_humidity_2m: Vec<Option<f32>>,
+    #[serde(default)]
+    wind_speed_10m: Vec<Option<f32>>,
+    #[serde(default)]
+    cloud_cover: Vec<Option<f32>>,
+    #[serde(default)]
+    sunshine_duration: Vec<Option<f32>>,
+    #[serde(default)]
+    precipitation: Vec<Option<f32>>,
+    #[serde(default)]
+    weather_code: Vec<Option<u8>>,
+    #[serde(default)]
+    is_day: Vec<Option<u8>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenMeteoApiError {
+    reason: String,
+}
+
+impl WeatherProvider for OpenMeteo {
+    fn name(&self) -> &str {
+        "Open-Meteo"
+    }
+
+    fn forecast_url(&self, lat: f32, lon: f32, start: DateTime<Utc>, end: DateTime<Utc>) -> String {
+        // Open-Meteo only accepts times without offset, so everything is requested in GMT
+        format!(
+            "{}?latitude={}&longitude={}&hourly={}&timezone=GMT&start_hour={}&end_hour={}",
+            self.endpoint,
+            lat,
+            lon,
+            HOURLY_VARIABLES,
+            start.format(TIME_FORMAT),
+            end.format(TIME_FORMAT)
+        )
+    }
+
+    fn parse_forecast(&self, body: &str) -> Result<Forecast, BackendError> {
+        match serde_json::from_str::<OpenMeteoResponse>(body) {
+            Ok(data) => to_forecast(data),
+            Err(e) => {
+                error!("Error parsing open-meteo data: {}", e);
+                match serde_json::from_str::<OpenMeteoApiError>(body) {
+                    Ok(api_error) => Err(BackendError::OpenMeteoError(api_error.reason)),
+                    Err(_) => Err(BackendError::ParseError(e.to_string())),
+                }
+            }
+        }
+    }
+}
+
+/// Maps WMO weather interpretation codes to conditions.
+fn to_condition(weather_code: u8) -> Option<Condition> {
+    match weather_code {
+        0..=3 => Some(Condition::Dry),
+        45 | 48 => Some(Condition::Fog),
+        51..=55 | 61..=65 | 80..=82 => Some(Condition::Rain),
+        56 | 57 | 66 | 67 => Some(Condition::FreezingRain),
+        71..=77 | 85 | 86 => Some(Condition::Snow),
+        95 => Some(Condition::Thunderstorm),
+        96 | 99 => Some(Condition::Hail),
+        other => {
+            warn!("Unknown weather code: {}", other);
+            None
+        }
+    }
+}
+
+/// Maps WMO weather interpretation codes to icons, assuming daytime if unknown.
+fn to_icon(weather_code: u8, is_day: Option<u8>) -> Option<Icon> {
+    let is_day = is_day.map_or(true, |is_day| is_day != 0);
+    match weather_code {
+        0 if is_day => Some(Icon::ClearDay),
+        0 => Some(Icon::ClearNight),
+        1 | 2 if is_day => Some(Icon::PartlyCloudyDay),
+        1 | 2 => Some(Icon::PartlyCloudyNight),
+        3 => Some(Icon::Cloudy),
+        45 | 48 => Some(Icon::Fog),
+        51..=55 | 61..=65 | 80..=82 => Some(Icon::Rain),
+        56 | 57 | 66 | 67 => Some(Icon::Sleet),
+        71..=77 | 85 | 86 => Some(Icon::Snow),
+        95 => Some(Icon::Thunderstorm),
+        96 | 99 => Some(Icon::Hail),
+        _ => None,
+    }
+}
+
+fn to_forecast(data: OpenMeteoResponse) -> Result<Forecast, BackendError> {
+    let station = Station {
+        name: format!("{:.2}, {:.2}", data.latitude, data.longitude),
+        lat: data.latitude,
+        lon: data.longitude,
+    };
+
+    let hourly = data.hourly;
+    let value = |values: &Vec<Option<f32>>, i: usize| values.get(i).cloned().flatten();
+
+    let mut hours = Vec::with_capacity(hourly.time.len());
+
+    for (i, time) in hourly.time.iter().enumerate() {
+        let timestamp = NaiveDateTime::parse_from_str(time, TIME_FORMAT)
+            .map_err(|e| BackendError::ParseError(format!("Invalid timestamp {}: {}", time, e)))?;
+        let weather_code = hourly.weather_code.get(i).cloned().flatten();
+        let is_day = hourly.is_day.get(i).cloned().flatten();
+        hours.push(HourlyRecord {
+            timestamp: Utc.from_utc_datetime(&timestamp),
+            temperature: value(&hourly.temperature_2m, i),
+            dew_point: value(&hourly.dew_point_2m, i),
+            relative_humidity: value(&hourly.relative_humidity_2m, i),
+            wind_speed: value(&hourly.wind_speed_10m, i),
+            cloud_cover: value(&hourly.cloud_cover, i),
+            // open-meteo reports sunshine in seconds per hour
+            sunshine: value(&hourly.sunshine_duration, i).map(|s| s / 60.0),
+            precipitation: value(&hourly.precipitation, i),
+            condition: weather_code.and_then(to_condition),
+            icon: weather_code.and_then(|code| to_icon(code, is_day)),
+        });
+    }
+
+    Ok(Forecast {
+        provider: None,
+        station: Some(station),
+        hours,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::backend::{accumulate_cold_phases, AnalysisOptions};
+
+    #[test]
+    fn test_parse_forecast() {
+        let data = std::fs::read_to_string("test/open_meteo.json").unwrap();
+        let forecast = OpenMeteo::default().parse_forecast(&data).unwrap();
+
+        assert_eq!(forecast.hours.len(), 24);
+        assert_eq!(
+            forecast.hours[0].timestamp,
+            Utc.ymd(2020, 10, 21).and_hms(0, 0, 0)
+        );
+        assert_eq!(forecast.hours[6].temperature, Some(2.4));
+        assert_eq!(forecast.hours[6].dew_point, Some(0.3));
+        assert_eq!(forecast.hours[6].wind_speed, Some(2.9));
+        assert_eq!(forecast.hours[6].cloud_cover, Some(0.0));
+        assert_eq!(forecast.hours[23].temperature, None);
+        assert_eq!(forecast.hours[2].icon, Some(Icon::ClearNight));
+        assert_eq!(forecast.hours[9].icon, Some(Icon::PartlyCloudyDay));
+        assert_eq!(forecast.hours[12].icon, Some(Icon::Cloudy));
+    }
+
+    #[test]
+    fn test_cold_phases() {
+        let data = std::fs::read_to_string("test/open_meteo.json").unwrap();
+        let forecast = OpenMeteo::default().parse_forecast(&data).unwrap();
+        let options = AnalysisOptions::new(5.0, 3.0);
+        let cold_phases =
+            accumulate_cold_phases(&options, chrono_tz::Europe::Berlin, &forecast).cold_phases;
+
+        assert_eq!(cold_phases.len(), 1);
+
+        let phase = &cold_phases[0];
+        assert_eq!(phase.min_temp, 2.4);
+        assert_eq!(phase.record_type, RecordType::Danger);
+        assert_eq!(
+            phase.start.with_timezone(&Utc),
+            Utc.ymd(2020, 10, 21).and_hms(4, 0, 0)
+        );
+        assert_eq!(
+            phase.end.with_timezone(&Utc),
+            Utc.ymd(2020, 10, 21).and_hms(9, 0, 0)
+        );
+    }
+
+    #[test]
+    fn test_api_error() {
+        let data = std::fs::read_to_string("test/open_meteo_error.json").unwrap();
+        let error = OpenMeteo::default().parse_forecast(&data).unwrap_err();
+
+        assert_eq!(
+            error,
+            BackendError::OpenMeteoError(
+                "Latitude must be in range of -90 to 90°. Given: 91.0.".to_owned()
+            )
+        );
+    }
+}
@@ -0,0 +1,123 @@
+use crate::common::*;
+use chrono::prelude::*;
+use chrono::Duration;
+
+/// A period of consecutive hours with similar conditions that can be grown hour by hour.
+pub trait Phase: Clone {
+    fn start(&self) -> DateTime<FixedOffset>;
+
+    fn end(&self) -> DateTime<FixedOffset>;
+
+    /// Extends the phase by a later phase or hour, keeping the worst conditions of both.
+    fn extend(&mut self, next: Self);
+}
+
+#[derive(Debug, Clone)]
+pub struct PhaseAccumulator<P> {
+    phases: Vec<P>,
+    current_phase: Option<P>,
+}
+
+impl<P: Phase> PhaseAccumulator<P> {
+    pub fn new() -> PhaseAccumulator<P> {
+        PhaseAccumulator {
+            phases: Vec::new(),
+            current_phase: None,
+        }
+    }
+
+    /// Adds the next hour, where `enters` tells if the hour starts a new phase and `stays` if it
+    /// continues a running one. Having separate conditions for both allows for hysteresis.
+    pub fn push(&mut self, hour: P, enters: bool, stays: bool) {
+        match self.current_phase.as_mut() {
+            // update current phase if there is one
+            Some(phase) if stays => phase.extend(hour),
+            // end current phase if there is one
+            Some(_) => self.phases.extend(self.current_phase.take()),
+            // start new phase
+            None if enters => self.current_phase = Some(hour),
+            None => (),
+        }
+    }
+
+    /// Merges phases separated by gaps no longer than `max_gap` and drops the ones shorter than
+    /// `min_duration`.
+    pub fn finish(self, max_gap: Duration, min_duration: Duration) -> Vec<P> {
+        let mut phases = self.phases;
+        phases.extend(self.current_phase);
+
+        let mut merged: Vec<P> = Vec::with_capacity(phases.len());
+
+        for phase in phases {
+            match merged.last_mut() {
+                Some(last) if phase.start() - last.end() <= max_gap => last.extend(phase),
+                _ => merged.push(phase),
+            }
+        }
+
+        merged
+            .into_iter()
+            .filter(|phase| phase.end() - phase.start() >= min_duration)
+            .collect()
+    }
+}
+
+impl<P: Phase> Default for PhaseAccumulator<P> {
+    fn default() -> Self {
+        PhaseAccumulator::new()
+    }
+}
+
+impl Phase for ColdPhase {
+    fn start(&self) -> DateTime<FixedOffset> {
+        self.start
+    }
+
+    fn end(&self) -> DateTime<FixedOffset> {
+        self.end
+    }
+
+    fn extend(&mut self, next: Self) {
+        if next.min_temp < self.min_temp {
+            self.min_temp = next.min_temp;
+            self.min_temp_at = next.min_temp_at;
+            self.trigger = next.trigger;
+        }
+        if next.record_type > self.record_type {
+            self.record_type = next.record_type;
+        }
+        if let Some(risk) = next.frost_risk {
+            let is_worse = self
+                .frost_risk
+                .as_ref()
+                .map_or(true, |r| risk.frost_point < r.frost_point);
+            if is_worse {
+                self.frost_risk = Some(risk);
+            }
+        }
+        self.samples.extend(next.samples);
+        self.end = next.end;
+    }
+}
+
+impl Phase for HeatPhase {
+    fn start(&self) -> DateTime<FixedOffset> {
+        self.start
+    }
+
+    fn end(&self) -> DateTime<FixedOffset> {
+        self.end
+    }
+
+    fn extend(&mut self, next: Self) {
+        if next.max_temp > self.max_temp {
+            self.max_temp = next.max_temp;
+            self.max_temp_at = next.max_temp_at;
+        }
+        if next.record_type > self.record_type {
+            self.record_type = next.record_type;
+        }
+        self.samples.extend(next.samples);
+        self.end = next.end;
+    }
+}
@@ -0,0 +1,13 @@
+use crate::common::*;
+use chrono::prelude::*;
+
+pub trait WeatherProvider: Send + Sync {
+    /// Human readable name of the provider, used in logs and responses.
+    fn name(&self) -> &str;
+
+    /// URL to request the hourly forecast for the given location and time window from.
+    fn forecast_url(&self, lat: f32, lon: f32, start: DateTime<Utc>, end: DateTime<Utc>) -> String;
+
+    /// Turns the body of a response to a `forecast_url` request into a provider-neutral forecast.
+    fn parse_forecast(&self, body: &str) -> Result<Forecast, BackendError>;
+}
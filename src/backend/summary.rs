@@ -0,0 +1,123 @@
+use crate::common::*;
+use chrono::prelude::*;
+use chrono::Duration;
+
+/// Hour of the day in local time at which a night begins.
+const NIGHT_START: u32 = 18;
+/// Hour of the day in local time at which a night ends, exclusively.
+const NIGHT_END: u32 = 9;
+
+#[derive(Debug, Clone, Default)]
+pub struct NightAccumulator {
+    nights: Vec<NightSummary>,
+}
+
+impl NightAccumulator {
+    pub fn new() -> NightAccumulator {
+        NightAccumulator { nights: Vec::new() }
+    }
+
+    /// Adds the next hour, where `temp` is the temperature compared to the thresholds and
+    /// `record_type` is only set if the hour is cold. Hours during the day are skipped.
+    pub fn push(
+        &mut self,
+        time: DateTime<FixedOffset>,
+        temp: f32,
+        record_type: Option<RecordType>,
+    ) {
+        let date = match night_of(&time) {
+            Some(date) => date,
+            None => return,
+        };
+        match self.nights.last_mut() {
+            Some(night) if night.date == date => {
+                if temp < night.min_temp {
+                    night.min_temp = temp;
+                    night.min_temp_at = time;
+                }
+                night.max_temp = night.max_temp.max(temp);
+                night.record_type = night.record_type.take().max(record_type);
+            }
+            _ => self.nights.push(NightSummary {
+                date,
+                min_temp: temp,
+                min_temp_at: time,
+                max_temp: temp,
+                record_type,
+            }),
+        }
+    }
+
+    pub fn finish(self) -> Vec<NightSummary> {
+        self.nights
+    }
+}
+
+/// The date of the evening the night containing `time` began on, if `time` is at night.
+fn night_of(time: &DateTime<FixedOffset>) -> Option<NaiveDate> {
+    let date = time.naive_local().date();
+    if time.hour() >= NIGHT_START {
+        Some(date)
+    } else if time.hour() < NIGHT_END {
+        Some(date - Duration::days(1))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::night_of;
+    use crate::backend::brightsky::BrightSky;
+    use crate::backend::provider::WeatherProvider;
+    use crate::backend::{accumulate_cold_phases, AnalysisOptions};
+    use crate::common::*;
+    use chrono::prelude::*;
+
+    #[test]
+    fn test_nights() {
+        let data = std::fs::read_to_string("test/test.json").unwrap();
+        let data = BrightSky::default().parse_forecast(&data).unwrap();
+        let options = AnalysisOptions::new(10.0, 7.0);
+        let nights = accumulate_cold_phases(&options, chrono_tz::Europe::Berlin, &data).nights;
+
+        assert_eq!(nights.len(), 2);
+
+        assert_eq!(nights[0].date, NaiveDate::from_ymd(2020, 4, 20));
+        assert_eq!(nights[0].min_temp, 6.7);
+        assert_eq!(
+            nights[0].min_temp_at.to_rfc3339(),
+            "2020-04-21T07:00:00+02:00"
+        );
+        assert_eq!(nights[0].max_temp, 10.6);
+        assert_eq!(nights[0].record_type, Some(RecordType::Danger));
+
+        assert_eq!(nights[1].date, NaiveDate::from_ymd(2020, 4, 21));
+        assert_eq!(nights[1].min_temp, 12.3);
+        assert_eq!(
+            nights[1].min_temp_at.to_rfc3339(),
+            "2020-04-22T02:00:00+02:00"
+        );
+        assert_eq!(nights[1].max_temp, 17.7);
+        assert_eq!(nights[1].record_type, None);
+    }
+
+    #[test]
+    fn test_night_hours() {
+        let night = |time: &str| night_of(&DateTime::parse_from_rfc3339(time).unwrap());
+        let evening = Some(NaiveDate::from_ymd(2020, 4, 20));
+
+        assert_eq!(
+            night("2020-04-20T08:59:00+02:00"),
+            Some(NaiveDate::from_ymd(2020, 4, 19))
+        );
+        assert_eq!(night("2020-04-20T09:00:00+02:00"), None);
+        assert_eq!(night("2020-04-20T12:00:00+02:00"), None);
+        assert_eq!(night("2020-04-20T17:59:00+02:00"), None);
+        assert_eq!(night("2020-04-20T18:00:00+02:00"), evening);
+        assert_eq!(night("2020-04-20T23:59:00+02:00"), evening);
+        assert_eq!(night("2020-04-21T00:00:00+02:00"), evening);
+        assert_eq!(night("2020-04-21T08:00:00+02:00"), evening);
+        assert_eq!(night("2020-04-21T09:00:00+02:00"), None);
+    }
+}
@@ -0,0 +1,42 @@
+use chrono::prelude::*;
+use chrono_tz::Tz;
+
+/// Looks up the IANA time zone of a location from an offline database, defaulting to UTC for
+/// locations that don't belong to any time zone (e.g. open sea).
+pub fn time_zone_at(lat: f32, lon: f32) -> Tz {
+    match tz_search::lookup(lat as f64, lon as f64) {
+        Some(name) => name.parse().unwrap_or_else(|e| {
+            warn!("Unknown time zone {}: {}", name, e);
+            Tz::UTC
+        }),
+        None => {
+            debug!("No time zone found for {}, {}", lat, lon);
+            Tz::UTC
+        }
+    }
+}
+
+/// Converts a timestamp to the given time zone, keeping only the offset so it can be serialized.
+pub fn to_local_time(timestamp: &DateTime<Utc>, tz: &Tz) -> DateTime<FixedOffset> {
+    let local = timestamp.with_timezone(tz);
+    local.with_timezone(&local.offset().fix())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_time_zone_at() {
+        assert_eq!(time_zone_at(49.88, 10.92), chrono_tz::Europe::Berlin);
+        assert_eq!(time_zone_at(40.71, -74.01), chrono_tz::America::New_York);
+    }
+
+    #[test]
+    fn test_to_local_time() {
+        let timestamp = Utc.ymd(2020, 4, 21).and_hms(2, 0, 0);
+        let local = to_local_time(&timestamp, &chrono_tz::Europe::Berlin);
+
+        assert_eq!(local.to_rfc3339(), "2020-04-21T04:00:00+02:00");
+    }
+}
@@ -0,0 +1,34 @@
+// the wind chill formula is only defined for cold, windy conditions
+const MAX_TEMPERATURE: f32 = 10.0;
+const MIN_WIND_SPEED: f32 = 4.8;
+
+/// Apparent temperature in °C from air temperature in °C and wind speed in km/h, using the wind
+/// chill formula of Environment Canada / the US National Weather Service. Outside the range the
+/// formula is defined for the air temperature is returned unchanged.
+pub fn apparent_temperature(temperature: f32, wind_speed: f32) -> f32 {
+    if temperature > MAX_TEMPERATURE || wind_speed < MIN_WIND_SPEED {
+        return temperature;
+    }
+
+    let wind = wind_speed.powf(0.16);
+    let wind_chill = 13.12 + 0.6215 * temperature - 11.37 * wind + 0.3965 * temperature * wind;
+
+    wind_chill.min(temperature)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_apparent_temperature() {
+        assert!((apparent_temperature(0.0, 20.0) - -5.2).abs() < 0.1);
+        assert!((apparent_temperature(-10.0, 40.0) - -20.8).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_outside_of_range() {
+        assert_eq!(apparent_temperature(12.0, 40.0), 12.0);
+        assert_eq!(apparent_temperature(-5.0, 2.0), -5.0);
+    }
+}
@@ -0,0 +1,202 @@
+use crate::common::*;
+use chrono::prelude::*;
+use chrono::Duration;
+use chrono_tz::Tz;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ForecastWindow {
+    pub start: DateTime<Tz>,
+    pub end: DateTime<Tz>,
+}
+
+impl ForecastWindow {
+    /// From now until noon in three days, in the time zone of `now`.
+    pub fn default_from(now: DateTime<Tz>) -> ForecastWindow {
+        let noon_in_three_days: DateTime<Tz> = (now + Duration::days(3))
+            .with_hour(12)
+            .and_then(|t| t.with_minute(0))
+            .and_then(|t| t.with_second(0))
+            .expect("always noon, can't be invalid");
+
+        ForecastWindow {
+            start: now,
+            end: noon_in_three_days,
+        }
+    }
+
+    /// Builds the window requested by a client, where `horizon` is the length of the window in
+    /// hours and mutually exclusive with `end`. Windows longer than `max_horizon`, ending further
+    /// than `max_horizon` in the future or starting more than a day in the past are rejected.
+    pub fn from_request(
+        now: DateTime<Tz>,
+        start: Option<DateTime<Tz>>,
+        end: Option<DateTime<Tz>>,
+        horizon: Option<u32>,
+        max_horizon: Duration,
+    ) -> Result<ForecastWindow, BackendError> {
+        let start = start.unwrap_or(now);
+        let too_far = || {
+            BackendError::InvalidRequest(format!(
+                "The forecast window must not reach further than {} hours into the future.",
+                max_horizon.num_hours()
+            ))
+        };
+
+        if let Some(horizon) = horizon {
+            if horizon as i64 > max_horizon.num_hours() {
+                return Err(too_far());
+            }
+        }
+
+        let window = match (end, horizon) {
+            (Some(_), Some(_)) => {
+                return Err(BackendError::InvalidRequest(
+                    "Only one of 'end' and 'horizon' may be specified.".to_owned(),
+                ))
+            }
+            (Some(end), None) => ForecastWindow { start, end },
+            (None, Some(horizon)) => ForecastWindow {
+                start,
+                end: start
+                    .checked_add_signed(Duration::hours(horizon as i64))
+                    .ok_or_else(too_far)?,
+            },
+            (None, None) if start == now => ForecastWindow::default_from(now),
+            (None, None) => ForecastWindow {
+                start,
+                end: start
+                    .checked_add_signed(Duration::days(3))
+                    .ok_or_else(too_far)?,
+            },
+        };
+
+        if window.end <= window.start {
+            return Err(BackendError::InvalidRequest(
+                "The forecast window must end after it starts.".to_owned(),
+            ));
+        }
+
+        if window.start < now - Duration::days(1) {
+            return Err(BackendError::InvalidRequest(
+                "The forecast window must not start more than a day in the past.".to_owned(),
+            ));
+        }
+
+        if window.end - window.start > max_horizon || window.end > now + max_horizon {
+            return Err(too_far());
+        }
+
+        Ok(window)
+    }
+
+    /// Only keeps the hourly records of the forecast that overlap with this window.
+    pub fn filter(&self, forecast: &Forecast) -> Forecast {
+        let hours = forecast
+            .hours
+            .iter()
+            .filter(|h| h.timestamp + Duration::hours(1) > self.start && h.timestamp < self.end)
+            .cloned()
+            .collect();
+
+        Forecast {
+            provider: forecast.provider.clone(),
+            station: forecast.station.clone(),
+            hours,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::backend::open_meteo::OpenMeteo;
+    use crate::backend::provider::WeatherProvider;
+    use chrono_tz::Europe::Berlin;
+
+    fn now() -> DateTime<Tz> {
+        Utc.ymd(2020, 10, 21)
+            .and_hms(14, 35, 0)
+            .with_timezone(&Berlin)
+    }
+
+    #[test]
+    fn test_default_ends_at_local_noon() {
+        let window = ForecastWindow::default_from(now());
+
+        assert_eq!(window.end, Berlin.ymd(2020, 10, 24).and_hms(12, 0, 0));
+        assert_eq!(
+            window.end.with_timezone(&Utc),
+            Utc.ymd(2020, 10, 24).and_hms(10, 0, 0)
+        );
+    }
+
+    #[test]
+    fn test_horizon() {
+        let window =
+            ForecastWindow::from_request(now(), None, None, Some(24), Duration::hours(240))
+                .unwrap();
+
+        assert_eq!(window.start, now());
+        assert_eq!(window.end, now() + Duration::hours(24));
+    }
+
+    #[test]
+    fn test_limits() {
+        let too_long =
+            ForecastWindow::from_request(now(), None, None, Some(241), Duration::hours(240));
+        assert!(too_long.is_err());
+
+        let end_before_start =
+            ForecastWindow::from_request(now(), None, Some(now()), None, Duration::hours(240));
+        assert!(end_before_start.is_err());
+
+        let both = ForecastWindow::from_request(
+            now(),
+            None,
+            Some(now() + Duration::hours(2)),
+            Some(2),
+            Duration::hours(240),
+        );
+        assert!(both.is_err());
+    }
+
+    #[test]
+    fn test_overflow() {
+        let huge_horizon =
+            ForecastWindow::from_request(now(), None, None, Some(u32::MAX), Duration::hours(240));
+        assert!(matches!(huge_horizon, Err(BackendError::InvalidRequest(_))));
+
+        let latest = chrono::MAX_DATETIME.with_timezone(&Berlin);
+        let late_start =
+            ForecastWindow::from_request(now(), Some(latest), None, None, Duration::hours(240));
+        assert!(matches!(late_start, Err(BackendError::InvalidRequest(_))));
+
+        let late_start =
+            ForecastWindow::from_request(now(), Some(latest), None, Some(1), Duration::hours(240));
+        assert!(matches!(late_start, Err(BackendError::InvalidRequest(_))));
+    }
+
+    #[test]
+    fn test_filter() {
+        let data = std::fs::read_to_string("test/open_meteo.json").unwrap();
+        let forecast = OpenMeteo::default().parse_forecast(&data).unwrap();
+
+        let window = ForecastWindow {
+            start: Utc
+                .ymd(2020, 10, 21)
+                .and_hms(3, 30, 0)
+                .with_timezone(&Berlin),
+            end: Utc
+                .ymd(2020, 10, 21)
+                .and_hms(6, 0, 0)
+                .with_timezone(&Berlin),
+        };
+        let filtered = window.filter(&forecast);
+
+        assert_eq!(filtered.hours.len(), 3);
+        assert_eq!(
+            filtered.hours[0].timestamp,
+            Utc.ymd(2020, 10, 21).and_hms(3, 0, 0)
+        );
+    }
+}
@@ -0,0 +1,286 @@
+use super::{ColdPhase, PushAlert, RecordType};
+use chrono::prelude::*;
+
+/// A drop of the minimum temperature of an announced phase by at least this many degrees is
+/// announced again.
+pub const SIGNIFICANT_DROP: f32 = 2.0;
+
+/// A cold phase the subscriber was alerted about, holding the most severe values announced.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct AnnouncedPhase {
+    pub start: DateTime<FixedOffset>,
+    pub end: DateTime<FixedOffset>,
+    pub record_type: RecordType,
+    pub min_temp: f32,
+}
+
+impl AnnouncedPhase {
+    fn overlaps(&self, phase: &ColdPhase) -> bool {
+        self.start < phase.end && phase.start < self.end
+    }
+}
+
+impl From<&ColdPhase> for AnnouncedPhase {
+    fn from(phase: &ColdPhase) -> Self {
+        AnnouncedPhase {
+            start: phase.start,
+            end: phase.end,
+            record_type: phase.record_type.clone(),
+            min_temp: phase.min_temp,
+        }
+    }
+}
+
+/// A change of the forecast worth alerting about.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", content = "phase", rename_all = "snake_case")]
+pub enum Announcement {
+    /// a cold phase appeared in the forecast
+    New(ColdPhase),
+    /// an announced phase became more severe, e.g. from `Warning` to `Danger`
+    Escalated(ColdPhase),
+    /// the minimum of an announced phase dropped by at least `SIGNIFICANT_DROP`
+    Colder(ColdPhase),
+    /// an announced phase that has not ended yet disappeared from the forecast
+    AllClear(AnnouncedPhase),
+}
+
+impl Announcement {
+    pub fn alert(&self, place_name: &str) -> PushAlert {
+        let (title, body, start) = match self {
+            Announcement::New(phase) => (
+                format!(
+                    "{} in {}",
+                    phase.record_type.to_string().to_uppercase(),
+                    place_name
+                ),
+                format!(
+                    "Temperatures as low as {} °C predicted{}, starting {}.",
+                    phase.min_temp,
+                    preset_suffix(phase),
+                    phase.start.format("%a %H:%M")
+                ),
+                phase.start,
+            ),
+            Announcement::Escalated(phase) => (
+                format!(
+                    "{} in {}",
+                    phase.record_type.to_string().to_uppercase(),
+                    place_name
+                ),
+                format!(
+                    "Now {}: temperatures as low as {} °C predicted{}, starting {}.",
+                    phase.record_type,
+                    phase.min_temp,
+                    preset_suffix(phase),
+                    phase.start.format("%a %H:%M")
+                ),
+                phase.start,
+            ),
+            Announcement::Colder(phase) => (
+                format!(
+                    "{} in {}",
+                    phase.record_type.to_string().to_uppercase(),
+                    place_name
+                ),
+                format!(
+                    "Colder than announced: temperatures as low as {} °C predicted{}, starting {}.",
+                    phase.min_temp,
+                    preset_suffix(phase),
+                    phase.start.format("%a %H:%M")
+                ),
+                phase.start,
+            ),
+            Announcement::AllClear(phase) => (
+                format!("ALL CLEAR in {}", place_name),
+                format!(
+                    "The {} for {} is no longer predicted.",
+                    phase.record_type,
+                    phase.start.format("%a %H:%M")
+                ),
+                phase.start,
+            ),
+        };
+
+        PushAlert {
+            title,
+            body,
+            // follow-ups replace the notification of the same phase
+            tag: format!("frost-{}-{}", place_name, start.timestamp()),
+        }
+    }
+}
+
+fn preset_suffix(phase: &ColdPhase) -> String {
+    match &phase.preset {
+        Some(preset) => format!(" for {}", preset),
+        None => String::new(),
+    }
+}
+
+/// Compares the forecast cold phases of a location to the ones announced before, returns what
+/// should be announced now and the announced phases to remember afterwards.
+///
+/// Phases are matched by overlapping time windows. Announced phases that are over are forgotten
+/// without an all clear.
+pub fn announcements(
+    announced: &[AnnouncedPhase],
+    phases: &[ColdPhase],
+    now: DateTime<Utc>,
+) -> (Vec<Announcement>, Vec<AnnouncedPhase>) {
+    let mut result = Vec::new();
+    let mut remembered: Vec<AnnouncedPhase> = Vec::new();
+
+    for phase in phases {
+        match announced.iter().find(|a| a.overlaps(phase)) {
+            None => {
+                result.push(Announcement::New(phase.clone()));
+                remembered.push(phase.into());
+            }
+            Some(previous) => {
+                if phase.record_type > previous.record_type {
+                    result.push(Announcement::Escalated(phase.clone()));
+                } else if phase.min_temp <= previous.min_temp - SIGNIFICANT_DROP {
+                    result.push(Announcement::Colder(phase.clone()));
+                }
+                remembered.push(AnnouncedPhase {
+                    start: phase.start,
+                    end: phase.end,
+                    record_type: phase.record_type.clone().max(previous.record_type.clone()),
+                    min_temp: phase.min_temp.min(previous.min_temp),
+                });
+            }
+        }
+    }
+
+    for previous in announced {
+        if previous.end > now && !phases.iter().any(|p| previous.overlaps(p)) {
+            result.push(Announcement::AllClear(previous.clone()));
+        }
+    }
+
+    (result, remembered)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::common::{TemperatureLevel, Trigger};
+
+    fn time(day: u32, hour: u32) -> DateTime<FixedOffset> {
+        FixedOffset::east(7200)
+            .ymd(2020, 4, day)
+            .and_hms(hour, 0, 0)
+    }
+
+    fn phase(start: u32, end: u32, record_type: RecordType, min_temp: f32) -> ColdPhase {
+        ColdPhase {
+            min_temp,
+            start: time(21, start),
+            end: time(21, end),
+            record_type,
+            warning_threshold: 5.0,
+            danger_threshold: 0.0,
+            level: TemperatureLevel::Air,
+            trigger: Trigger::default(),
+            frost_risk: None,
+            min_temp_at: None,
+            samples: vec![],
+            preset: None,
+        }
+    }
+
+    fn now() -> DateTime<Utc> {
+        time(20, 18).with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_new_phase() {
+        let phases = vec![phase(2, 7, RecordType::Warning, 2.0)];
+        let (result, announced) = announcements(&[], &phases, now());
+
+        assert_eq!(result, vec![Announcement::New(phases[0].clone())]);
+        assert_eq!(announced, vec![AnnouncedPhase::from(&phases[0])]);
+    }
+
+    #[test]
+    fn test_unchanged_phase_is_not_repeated() {
+        let announced = vec![AnnouncedPhase::from(&phase(2, 7, RecordType::Warning, 2.0))];
+        let phases = vec![phase(3, 8, RecordType::Warning, 1.0)];
+        let (result, remembered) = announcements(&announced, &phases, now());
+
+        assert_eq!(result, vec![]);
+        assert_eq!(remembered[0].start, time(21, 3));
+        assert_eq!(remembered[0].min_temp, 1.0);
+    }
+
+    #[test]
+    fn test_escalation() {
+        let announced = vec![AnnouncedPhase::from(&phase(2, 7, RecordType::Warning, 2.0))];
+        let phases = vec![phase(2, 7, RecordType::Danger, -1.0)];
+        let (result, remembered) = announcements(&announced, &phases, now());
+
+        assert_eq!(result, vec![Announcement::Escalated(phases[0].clone())]);
+        assert_eq!(remembered[0].record_type, RecordType::Danger);
+
+        // improving and worsening again stays quiet
+        let phases = vec![phase(2, 7, RecordType::Warning, 1.0)];
+        let (_, remembered) = announcements(&remembered, &phases, now());
+        let phases = vec![phase(2, 7, RecordType::Danger, -1.0)];
+        assert_eq!(announcements(&remembered, &phases, now()).0, vec![]);
+    }
+
+    #[test]
+    fn test_significant_drop() {
+        let announced = vec![AnnouncedPhase::from(&phase(2, 7, RecordType::Danger, -1.0))];
+
+        let phases = vec![phase(2, 7, RecordType::Danger, -2.5)];
+        assert_eq!(announcements(&announced, &phases, now()).0, vec![]);
+
+        let phases = vec![phase(2, 7, RecordType::Danger, -3.0)];
+        assert_eq!(
+            announcements(&announced, &phases, now()).0,
+            vec![Announcement::Colder(phases[0].clone())]
+        );
+    }
+
+    #[test]
+    fn test_all_clear() {
+        let announced = vec![AnnouncedPhase::from(&phase(2, 7, RecordType::Warning, 2.0))];
+        let (result, remembered) = announcements(&announced, &[], now());
+
+        assert_eq!(result, vec![Announcement::AllClear(announced[0].clone())]);
+        assert_eq!(remembered, vec![]);
+    }
+
+    #[test]
+    fn test_past_phase_is_forgotten() {
+        let announced = vec![AnnouncedPhase::from(&phase(2, 7, RecordType::Warning, 2.0))];
+        let later = time(21, 9).with_timezone(&Utc);
+        let (result, remembered) = announcements(&announced, &[], later);
+
+        assert_eq!(result, vec![]);
+        assert_eq!(remembered, vec![]);
+    }
+
+    #[test]
+    fn test_alert() {
+        let alert = Announcement::New(phase(4, 7, RecordType::Danger, 6.7)).alert("Garden");
+        assert_eq!(alert.title, "DANGER in Garden");
+        assert_eq!(
+            alert.body,
+            "Temperatures as low as 6.7 °C predicted, starting Tue 04:00."
+        );
+        assert_eq!(alert.tag, "frost-Garden-1587434400");
+
+        let clear =
+            Announcement::AllClear(AnnouncedPhase::from(&phase(4, 7, RecordType::Danger, 6.7)));
+        let clear = clear.alert("Garden");
+        assert_eq!(clear.title, "ALL CLEAR in Garden");
+        assert_eq!(
+            clear.body,
+            "The danger for Tue 04:00 is no longer predicted."
+        );
+        assert_eq!(clear.tag, alert.tag);
+    }
+}
@@ -0,0 +1,68 @@
+use super::HourlyRecord;
+
+// radiative cooling of the ground below 2 m air temperature in a clear, calm night
+const MAX_COOLING: f32 = 5.0;
+// even an overcast sky lets some radiation escape
+const OVERCAST_COOLING_FACTOR: f32 = 0.15;
+// below this wind speed the air near the ground doesn't mix at all
+const CALM_WIND_SPEED: f32 = 5.0;
+// above this wind speed the air near the ground is mixed thoroughly
+const MIXING_WIND_SPEED: f32 = 25.0;
+
+/// Estimates the minimum temperature at ground/grass level from the 2 m forecast, using a simple
+/// radiative cooling heuristic. Missing values are treated as favourable for cooling.
+pub fn ground_temperature(hour: &HourlyRecord) -> Option<f32> {
+    let temperature = hour.temperature?;
+
+    let cloud_factor = hour.cloud_cover.map_or(1.0, |cloud_cover| {
+        1.0 - (1.0 - OVERCAST_COOLING_FACTOR) * (cloud_cover / 100.0).min(1.0).max(0.0)
+    });
+
+    let wind_factor = hour.wind_speed.map_or(1.0, |wind_speed| {
+        ((MIXING_WIND_SPEED - wind_speed) / (MIXING_WIND_SPEED - CALM_WIND_SPEED))
+            .min(1.0)
+            .max(0.0)
+    });
+
+    // sunshine is given in minutes per hour, the sun warms the ground instead of letting it cool
+    let night_factor = hour
+        .sunshine
+        .map_or(1.0, |sunshine| 1.0 - (sunshine / 60.0).min(1.0).max(0.0));
+
+    Some(temperature - MAX_COOLING * cloud_factor * wind_factor * night_factor)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::prelude::*;
+
+    fn hour(cloud_cover: f32, wind_speed: f32, sunshine: f32) -> HourlyRecord {
+        HourlyRecord {
+            timestamp: Utc.ymd(2020, 10, 21).and_hms(4, 0, 0),
+            temperature: Some(3.0),
+            dew_point: None,
+            relative_humidity: None,
+            wind_speed: Some(wind_speed),
+            cloud_cover: Some(cloud_cover),
+            sunshine: Some(sunshine),
+            precipitation: None,
+            condition: None,
+            icon: None,
+        }
+    }
+
+    #[test]
+    fn test_clear_calm_night() {
+        assert_eq!(ground_temperature(&hour(0.0, 2.0, 0.0)), Some(-2.0));
+    }
+
+    #[test]
+    fn test_overcast_windy_or_sunny() {
+        let overcast = ground_temperature(&hour(100.0, 2.0, 0.0)).unwrap();
+        assert!((overcast - 2.25).abs() < 0.001);
+
+        assert_eq!(ground_temperature(&hour(0.0, 30.0, 0.0)), Some(3.0));
+        assert_eq!(ground_temperature(&hour(0.0, 2.0, 60.0)), Some(3.0));
+    }
+}
@@ -1,12 +1,87 @@
 use chrono::prelude::*;
 use std::fmt;
+use std::str::FromStr;
+
+mod announcements;
+mod ground;
+pub use announcements::*;
+pub use ground::*;
 
 pub const LOCATION_KEY: &'static str = "location";
 pub const THRESHOLD_KEY: &'static str = "thresholds";
+pub const HORIZON_KEY: &'static str = "horizon";
+pub const LEVEL_KEY: &'static str = "level";
+pub const WIND_CHILL_KEY: &'static str = "wind_chill";
+pub const HEAT_THRESHOLD_KEY: &'static str = "heat_thresholds";
+pub const PRESET_KEY: &'static str = "preset";
+pub const PLACES_KEY: &'static str = "places";
+/// Holds the id of the push subscription while push alerts are on.
+pub const PUSH_ALERTS_KEY: &'static str = "push_alerts";
+pub const ANNOUNCED_KEY: &'static str = "announced_phases";
 
 pub type BackendResult = Result<BackendResponse, BackendError>;
+pub type ForecastResult = Result<Forecast, BackendError>;
+pub type BatchResult = Result<Vec<BackendResult>, BackendError>;
 pub type Thresholds = (f32, f32);
 
+/// Thresholds and evaluation level suited for a type of plant.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlantPreset {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub thresholds: Thresholds,
+    pub level: TemperatureLevel,
+}
+
+pub const PLANT_PRESETS: &[PlantPreset] = &[
+    PlantPreset {
+        id: "tomatoes",
+        name: "tomatoes",
+        thresholds: (10.0, 3.0),
+        level: TemperatureLevel::Air,
+    },
+    PlantPreset {
+        id: "peppers",
+        name: "peppers",
+        thresholds: (10.0, 5.0),
+        level: TemperatureLevel::Air,
+    },
+    PlantPreset {
+        id: "citrus-pots",
+        name: "citrus in pots",
+        thresholds: (3.0, -2.0),
+        level: TemperatureLevel::Air,
+    },
+    PlantPreset {
+        id: "apple-blossom",
+        name: "apple blossom",
+        thresholds: (0.0, -2.0),
+        level: TemperatureLevel::Air,
+    },
+    PlantPreset {
+        id: "strawberry-blossom",
+        name: "strawberry blossom",
+        thresholds: (1.0, -1.0),
+        level: TemperatureLevel::Ground,
+    },
+    PlantPreset {
+        id: "potatoes",
+        name: "potatoes",
+        thresholds: (2.0, -1.0),
+        level: TemperatureLevel::Ground,
+    },
+    PlantPreset {
+        id: "seedlings",
+        name: "seedlings",
+        thresholds: (4.0, 0.0),
+        level: TemperatureLevel::Ground,
+    },
+];
+
+pub fn plant_preset(id: &str) -> Option<&'static PlantPreset> {
+    PLANT_PRESETS.iter().find(|preset| preset.id == id)
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum LocationStatus {
     RequestDeviceLocation,
@@ -40,22 +115,419 @@ pub enum Status {
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct BackendResponse {
     pub location: Option<String>,
+    #[serde(default)]
+    pub provider: Option<String>,
+    #[serde(default)]
+    pub time_zone: Option<String>,
     pub cold_phases: Vec<ColdPhase>,
+    #[serde(default)]
+    pub heat_phases: Vec<HeatPhase>,
+    #[serde(default)]
+    pub nights: Vec<NightSummary>,
+    #[serde(default)]
+    pub ice_hazards: Vec<IceHazard>,
+}
+
+/// A single location of a batch request, either `thresholds` or `preset` must be set.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct BatchEntry {
+    pub lat: f32,
+    pub lon: f32,
+    #[serde(default)]
+    pub thresholds: Option<Thresholds>,
+    #[serde(default)]
+    pub preset: Option<String>,
+    #[serde(default)]
+    pub level: Option<TemperatureLevel>,
+    #[serde(default)]
+    pub horizon: Option<u32>,
+}
+
+/// A browser push subscription as returned by `PushSubscription.toJSON()`.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct PushSubscription {
+    pub endpoint: String,
+    pub keys: PushKeys,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct PushKeys {
+    pub p256dh: String,
+    pub auth: String,
+}
+
+/// Request to be alerted about cold phases at a location even while the app is closed.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct AlertSubscription {
+    /// assigned by the server when the subscription is stored
+    #[serde(default)]
+    pub id: Option<i64>,
+    pub channel: AlertChannel,
+    /// name shown in alerts
+    pub place_name: String,
+    pub location: BatchEntry,
+    #[serde(default)]
+    pub quiet_hours: Option<QuietHours>,
+    /// maintained by the server
+    #[serde(default)]
+    pub last_alert: Option<AlertState>,
+}
+
+/// How alerts are delivered.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AlertChannel {
+    Push(PushSubscription),
+    /// digest emails of the upcoming cold phases
+    Email {
+        address: String,
+    },
+    /// signed JSON requests, see `WebhookPayload`
+    Webhook {
+        url: String,
+        /// key of the HMAC-SHA256 signature in the `X-Frost-Signature` header
+        secret: String,
+    },
+}
+
+impl AlertChannel {
+    /// Identifies the recipient, there is only one subscription per recipient.
+    pub fn key(&self) -> &str {
+        match self {
+            AlertChannel::Push(subscription) => &subscription.endpoint,
+            AlertChannel::Email { address } => address,
+            AlertChannel::Webhook { url, .. } => url,
+        }
+    }
+}
+
+/// A stored subscription as returned by the server, without the keys and secrets of its channel.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct SubscriptionInfo {
+    pub id: Option<i64>,
+    pub channel: ChannelInfo,
+    pub place_name: String,
+    pub location: BatchEntry,
+    pub quiet_hours: Option<QuietHours>,
+    pub last_alert: Option<AlertState>,
+}
+
+impl From<AlertSubscription> for SubscriptionInfo {
+    fn from(subscription: AlertSubscription) -> Self {
+        SubscriptionInfo {
+            id: subscription.id,
+            channel: subscription.channel.into(),
+            place_name: subscription.place_name,
+            location: subscription.location,
+            quiet_hours: subscription.quiet_hours,
+            last_alert: subscription.last_alert,
+        }
+    }
+}
+
+/// The recipient of an `AlertChannel`.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChannelInfo {
+    Push { endpoint: String },
+    Email { address: String },
+    Webhook { url: String },
+}
+
+impl From<AlertChannel> for ChannelInfo {
+    fn from(channel: AlertChannel) -> Self {
+        match channel {
+            AlertChannel::Push(subscription) => ChannelInfo::Push {
+                endpoint: subscription.endpoint,
+            },
+            AlertChannel::Email { address } => ChannelInfo::Email { address },
+            AlertChannel::Webhook { url, .. } => ChannelInfo::Webhook { url },
+        }
+    }
+}
+
+/// Hours of the day in local time of the location during which no alerts are sent, `end` is
+/// exclusive and may be before `start` to span midnight.
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub struct QuietHours {
+    pub start: u32,
+    pub end: u32,
+}
+
+impl QuietHours {
+    pub fn contains(&self, hour: u32) -> bool {
+        if self.start <= self.end {
+            self.start <= hour && hour < self.end
+        } else {
+            hour >= self.start || hour < self.end
+        }
+    }
+}
+
+/// What the subscriber was last alerted about.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct AlertState {
+    pub sent_at: DateTime<Utc>,
+    #[serde(default)]
+    pub phases: Vec<AnnouncedPhase>,
+}
+
+/// Payload of a push message, displayed as notification by the service worker.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct PushAlert {
+    pub title: String,
+    pub body: String,
+    pub tag: String,
+}
+
+/// Body of a webhook request, sent once per announcement.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct WebhookPayload {
+    pub place_name: String,
+    /// what triggered the request, along with the phase concerned
+    pub trigger: Announcement,
+    pub alert: PushAlert,
+    pub response: BackendResponse,
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct ColdPhase {
     pub min_temp: f32,
-    pub start: DateTime<Local>,
-    pub end: DateTime<Local>,
+    pub start: DateTime<FixedOffset>,
+    pub end: DateTime<FixedOffset>,
     pub record_type: RecordType,
     pub warning_threshold: f32,
     pub danger_threshold: f32,
+    #[serde(default)]
+    pub level: TemperatureLevel,
+    #[serde(default)]
+    pub trigger: Trigger,
+    #[serde(default)]
+    pub frost_risk: Option<FrostRisk>,
+    #[serde(default)]
+    pub min_temp_at: Option<DateTime<FixedOffset>>,
+    #[serde(default)]
+    pub samples: Vec<PhaseSample>,
+    /// name of the plant preset the thresholds were taken from
+    #[serde(default)]
+    pub preset: Option<String>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// Aggregate of one night, running from 18:00 to 09:00 in local time.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct NightSummary {
+    /// date of the evening the night begins on
+    pub date: NaiveDate,
+    /// lowest temperature compared to the thresholds
+    pub min_temp: f32,
+    pub min_temp_at: DateTime<FixedOffset>,
+    /// highest temperature compared to the thresholds
+    pub max_temp: f32,
+    /// worst record type reached, if any hour was cold
+    pub record_type: Option<RecordType>,
+}
+
+/// A single hour of a phase with the values it was classified by.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct PhaseSample {
+    pub timestamp: DateTime<FixedOffset>,
+    /// temperature at the level the thresholds are applied at
+    pub temperature: Option<f32>,
+    /// wind chill, only if enabled
+    pub apparent_temperature: Option<f32>,
+    pub dew_point: Option<f32>,
+    pub relative_humidity: Option<f32>,
+    pub wind_speed: Option<f32>,
+    pub cloud_cover: Option<f32>,
+}
+
+/// The metric that caused the lowest temperature of a phase.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Trigger {
+    Temperature,
+    WindChill,
+}
+
+impl Default for Trigger {
+    fn default() -> Self {
+        Trigger::Temperature
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct HeatPhase {
+    pub max_temp: f32,
+    pub start: DateTime<FixedOffset>,
+    pub end: DateTime<FixedOffset>,
+    pub record_type: RecordType,
+    pub warning_threshold: f32,
+    pub danger_threshold: f32,
+    #[serde(default)]
+    pub max_temp_at: Option<DateTime<FixedOffset>>,
+    #[serde(default)]
+    pub samples: Vec<PhaseSample>,
+}
+
+/// The height the thresholds are applied at, either the forecast air temperature at 2 m or the
+/// estimated temperature at ground level.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TemperatureLevel {
+    Air,
+    Ground,
+}
+
+impl Default for TemperatureLevel {
+    fn default() -> Self {
+        TemperatureLevel::Air
+    }
+}
+
+impl fmt::Display for TemperatureLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemperatureLevel::Air => write!(f, "air"),
+            TemperatureLevel::Ground => write!(f, "ground"),
+        }
+    }
+}
+
+impl FromStr for TemperatureLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "air" => Ok(TemperatureLevel::Air),
+            "ground" => Ok(TemperatureLevel::Ground),
+            other => Err(format!("Unknown temperature level '{}'", other)),
+        }
+    }
+}
+
+/// Conditions that make hoar frost likely, taken from the hour with the lowest frost point.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct FrostRisk {
+    pub frost_point: f32,
+    pub dew_point_spread: f32,
+    pub relative_humidity: Option<f32>,
+    pub cloud_cover: Option<f32>,
+    pub wind_speed: Option<f32>,
+}
+
+impl fmt::Display for FrostRisk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "frost point {:.1} °C, dew point {:.1} °C below air temperature",
+            self.frost_point, self.dew_point_spread
+        )?;
+        if let Some(cloud_cover) = self.cloud_cover {
+            write!(f, ", {:.0} % cloud cover", cloud_cover)?;
+        }
+        if let Some(wind_speed) = self.wind_speed {
+            write!(f, ", wind {:.0} km/h", wind_speed)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Forecast {
+    pub provider: Option<String>,
+    pub station: Option<Station>,
+    pub hours: Vec<HourlyRecord>,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Station {
+    pub name: String,
+    pub lat: f32,
+    pub lon: f32,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct HourlyRecord {
+    pub timestamp: DateTime<Utc>,
+    pub temperature: Option<f32>,
+    pub dew_point: Option<f32>,
+    pub relative_humidity: Option<f32>,
+    pub wind_speed: Option<f32>,
+    pub cloud_cover: Option<f32>,
+    pub sunshine: Option<f32>,
+    pub precipitation: Option<f32>,
+    pub condition: Option<Condition>,
+    #[serde(default)]
+    pub icon: Option<Icon>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Condition {
+    Dry,
+    Fog,
+    Rain,
+    FreezingRain,
+    Sleet,
+    Snow,
+    Hail,
+    Thunderstorm,
+}
+
+impl Condition {
+    pub fn is_frozen(&self) -> bool {
+        matches!(self, Condition::Snow | Condition::Sleet | Condition::Hail)
+    }
+}
+
+/// Weather icons, named like the ones used by Bright Sky.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Icon {
+    ClearDay,
+    ClearNight,
+    PartlyCloudyDay,
+    PartlyCloudyNight,
+    Cloudy,
+    Fog,
+    Wind,
+    Rain,
+    Sleet,
+    Snow,
+    Hail,
+    Thunderstorm,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct IceHazard {
+    pub kind: IceHazardKind,
+    pub start: DateTime<FixedOffset>,
+    pub end: DateTime<FixedOffset>,
+    pub min_temp: f32,
+    /// total precipitation in mm
+    pub precipitation: f32,
+}
+
+// ordered by severity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum IceHazardKind {
+    Snow,
+    BlackIce,
+    FreezingRain,
+}
+
+impl fmt::Display for IceHazardKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IceHazardKind::Snow => write!(f, "snow"),
+            IceHazardKind::BlackIce => write!(f, "black ice"),
+            IceHazardKind::FreezingRain => write!(f, "freezing rain"),
+        }
+    }
+}
+
+// ordered by severity
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum RecordType {
     Warning,
+    GroundFrost,
     Danger,
 }
 
@@ -63,6 +535,7 @@ impl fmt::Display for RecordType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             RecordType::Warning => write!(f, "warning"),
+            RecordType::GroundFrost => write!(f, "ground frost"),
             RecordType::Danger => write!(f, "danger"),
         }
     }
@@ -71,14 +544,22 @@ impl fmt::Display for RecordType {
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum BackendError {
     BrightskyError(BrightskyApiError),
+    OpenMeteoError(String),
     NetworkError(String),
+    ParseError(String),
+    InvalidRequest(String),
+    StorageError(String),
 }
 
 impl fmt::Display for BackendError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             BackendError::BrightskyError(e) => e.fmt(f),
+            BackendError::OpenMeteoError(e) => e.fmt(f),
             BackendError::NetworkError(e) => e.fmt(f),
+            BackendError::ParseError(e) => e.fmt(f),
+            BackendError::InvalidRequest(e) => e.fmt(f),
+            BackendError::StorageError(e) => e.fmt(f),
         }
     }
 }
@@ -134,6 +615,24 @@ impl Place {
     }
 }
 
+/// A place the user saved for quick access, with the thresholds to use there.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedPlace {
+    pub place: Place,
+    #[serde(default)]
+    pub thresholds: Option<Thresholds>,
+    #[serde(default)]
+    pub level: Option<TemperatureLevel>,
+    #[serde(default)]
+    pub preset: Option<String>,
+}
+
+impl SavedPlace {
+    pub fn is_at(&self, place: &Place) -> bool {
+        self.place == *place
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Geometry {
     pub location: Location,
@@ -0,0 +1,225 @@
+use crate::common::*;
+use chrono::prelude::*;
+use yew::prelude::*;
+use yew::virtual_dom::VNode;
+
+const WIDTH: f32 = 800.0;
+const HEIGHT: f32 = 300.0;
+const MARGIN_LEFT: f32 = 40.0;
+const MARGIN_RIGHT: f32 = 10.0;
+const MARGIN_TOP: f32 = 10.0;
+const MARGIN_BOTTOM: f32 = 24.0;
+/// degrees added above and below the plotted temperatures
+const PADDING: f32 = 2.0;
+/// degrees between two horizontal grid lines
+const GRID_STEP: f32 = 5.0;
+
+#[derive(Debug, Clone, Properties, PartialEq)]
+pub struct Props {
+    pub hours: Vec<HourlyRecord>,
+    pub thresholds: Thresholds,
+    pub cold_phases: Vec<ColdPhase>,
+    /// the level the thresholds were applied at, decides which temperatures are plotted
+    pub level: TemperatureLevel,
+    /// timestamps of the response, their UTC offsets place the day lines at local midnight
+    pub local_times: Vec<DateTime<FixedOffset>>,
+}
+
+pub struct Chart {
+    props: Props,
+}
+
+impl Component for Chart {
+    type Message = ();
+    type Properties = Props;
+
+    fn create(props: Self::Properties, _link: ComponentLink<Self>) -> Self {
+        Chart { props }
+    }
+
+    fn update(&mut self, _msg: Self::Message) -> ShouldRender {
+        false
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        if self.props != props {
+            self.props = props;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn view(&self) -> Html {
+        let level = self.props.level;
+        let scale = match Scale::new(&self.props.hours, level, self.props.thresholds) {
+            Some(scale) => scale,
+            None => return html! {},
+        };
+
+        let (warning_threshold, danger_threshold) = self.props.thresholds;
+        let bottom = HEIGHT - MARGIN_BOTTOM;
+        let warning_y = scale.y(warning_threshold).max(MARGIN_TOP).min(bottom);
+        let danger_y = scale.y(danger_threshold).max(MARGIN_TOP).min(bottom);
+
+        let phases: Vec<VNode> = self
+            .props
+            .cold_phases
+            .iter()
+            .map(|phase| {
+                let x1 = scale.x(&phase.start.with_timezone(&Utc));
+                let x2 = scale.x(&phase.end.with_timezone(&Utc));
+                rect("chart-phase", x1, MARGIN_TOP, x2 - x1, bottom - MARGIN_TOP)
+            })
+            .collect();
+
+        let grid: Vec<VNode> = scale
+            .grid_temperatures()
+            .map(|temp| {
+                let y = scale.y(temp);
+                html! {
+                    <g>
+                        <line class="chart-grid" x1={MARGIN_LEFT} y1={y} x2={WIDTH - MARGIN_RIGHT} y2={y} />
+                        <text class="chart-label" x={MARGIN_LEFT - 4.0} y={y} text-anchor="end" dominant-baseline="middle">{format!("{}°", temp)}</text>
+                    </g>
+                }
+            })
+            .collect();
+
+        let days: Vec<VNode> = self
+            .props
+            .hours
+            .iter()
+            .map(|hour| local_time(&self.props.local_times, &hour.timestamp))
+            .filter(|time| time.hour() == 0)
+            .map(|time| {
+                let x = scale.x(&time.with_timezone(&Utc));
+                html! {
+                    <g>
+                        <line class="chart-day" x1={x} y1={MARGIN_TOP} x2={x} y2={bottom} />
+                        <text class="chart-label" x={x} y={HEIGHT - 4.0} text-anchor="middle">{time.format("%a %d")}</text>
+                    </g>
+                }
+            })
+            .collect();
+
+        html! {
+            <svg class="chart" viewBox={format!("0 0 {} {}", WIDTH, HEIGHT)}>
+                {rect("chart-warning", MARGIN_LEFT, warning_y, WIDTH - MARGIN_LEFT - MARGIN_RIGHT, bottom - warning_y)}
+                {rect("chart-danger", MARGIN_LEFT, danger_y, WIDTH - MARGIN_LEFT - MARGIN_RIGHT, bottom - danger_y)}
+                { phases }
+                { grid }
+                { days }
+                <path class="chart-line" d={line_path(&scale, &self.props.hours, level)} />
+            </svg>
+        }
+    }
+}
+
+/// Maps timestamps and temperatures to coordinates within the chart area.
+struct Scale {
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    min_temp: f32,
+    max_temp: f32,
+}
+
+impl Scale {
+    fn new(
+        hours: &[HourlyRecord],
+        level: TemperatureLevel,
+        (warning_threshold, danger_threshold): Thresholds,
+    ) -> Option<Scale> {
+        let start = hours.first()?.timestamp;
+        let end = hours.last()?.timestamp;
+        if end <= start {
+            return None;
+        }
+
+        let temps = hours.iter().filter_map(|h| temperature(h, level));
+        let min_temp = temps
+            .clone()
+            .fold(warning_threshold.min(danger_threshold), f32::min);
+        let max_temp = temps.fold(warning_threshold.max(danger_threshold), f32::max);
+
+        Some(Scale {
+            start,
+            end,
+            min_temp: min_temp - PADDING,
+            max_temp: max_temp + PADDING,
+        })
+    }
+
+    fn x(&self, time: &DateTime<Utc>) -> f32 {
+        let total = (self.end - self.start).num_seconds() as f32;
+        let offset = (*time - self.start).num_seconds() as f32;
+        let width = WIDTH - MARGIN_LEFT - MARGIN_RIGHT;
+        (MARGIN_LEFT + offset / total * width)
+            .max(MARGIN_LEFT)
+            .min(WIDTH - MARGIN_RIGHT)
+    }
+
+    fn y(&self, temp: f32) -> f32 {
+        let height = HEIGHT - MARGIN_TOP - MARGIN_BOTTOM;
+        MARGIN_TOP + (self.max_temp - temp) / (self.max_temp - self.min_temp) * height
+    }
+
+    fn grid_temperatures(&self) -> impl Iterator<Item = f32> {
+        let first = (self.min_temp / GRID_STEP).ceil() as i32;
+        let last = (self.max_temp / GRID_STEP).floor() as i32;
+        (first..=last).map(|step| step as f32 * GRID_STEP)
+    }
+}
+
+/// The temperature of the hour at the level the thresholds are applied at.
+fn temperature(hour: &HourlyRecord, level: TemperatureLevel) -> Option<f32> {
+    match level {
+        TemperatureLevel::Air => hour.temperature,
+        TemperatureLevel::Ground => ground_temperature(hour),
+    }
+}
+
+/// Converts the time to the UTC offset of the latest of `local_times` before it, or of the first
+/// one if there is none, so changes between summer and winter time are picked up.
+fn local_time(
+    local_times: &[DateTime<FixedOffset>],
+    time: &DateTime<Utc>,
+) -> DateTime<FixedOffset> {
+    let offset = local_times
+        .iter()
+        .filter(|local| **local <= *time)
+        .max()
+        .or_else(|| local_times.iter().min())
+        .map_or(FixedOffset::east(0), |local| *local.offset());
+    time.with_timezone(&offset)
+}
+
+/// Builds the path of the temperature curve, leaving gaps where temperatures are missing.
+fn line_path(scale: &Scale, hours: &[HourlyRecord], level: TemperatureLevel) -> String {
+    let mut path = String::new();
+    let mut drawing = false;
+
+    for hour in hours {
+        match temperature(hour, level) {
+            Some(temp) => {
+                let command = if drawing { 'L' } else { 'M' };
+                path.push_str(&format!(
+                    "{}{:.1},{:.1} ",
+                    command,
+                    scale.x(&hour.timestamp),
+                    scale.y(temp)
+                ));
+                drawing = true;
+            }
+            None => drawing = false,
+        }
+    }
+
+    path
+}
+
+fn rect(class: &'static str, x: f32, y: f32, width: f32, height: f32) -> VNode {
+    html! {
+        <rect class={class} x={x} y={y} width={width.max(0.0)} height={height.max(0.0)} />
+    }
+}
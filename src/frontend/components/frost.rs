@@ -1,6 +1,11 @@
+use super::chart::*;
+use super::hazard::*;
+use super::heat::*;
+use super::nights::*;
 use super::record::*;
 use crate::common::WeatherDataStatus;
 use crate::common::*;
+use chrono::prelude::*;
 use yew::prelude::*;
 use yew::virtual_dom::VNode;
 
@@ -11,6 +16,9 @@ pub struct Frost {
 #[derive(Debug, Clone, Properties, PartialEq)]
 pub struct Props {
     pub weather: Option<WeatherDataStatus>,
+    pub forecast: Option<Forecast>,
+    pub thresholds: Thresholds,
+    pub level: TemperatureLevel,
 }
 
 impl Component for Frost {
@@ -33,19 +41,40 @@ impl Component for Frost {
 
     fn view(&self) -> Html {
         if let Some(WeatherDataStatus::WeatherDataRetrieved(Ok(data))) = &self.props.weather {
-            let records: Vec<VNode> = data.cold_phases.iter().map(to_record).collect();
+            let mut records: Vec<(&DateTime<FixedOffset>, VNode)> = data
+                .cold_phases
+                .iter()
+                .map(|p| (&p.start, to_record(p)))
+                .chain(
+                    data.heat_phases
+                        .iter()
+                        .map(|p| (&p.start, to_heat_record(p))),
+                )
+                .chain(data.ice_hazards.iter().map(|h| (&h.start, to_hazard(h))))
+                .collect();
+            records.sort_by_key(|(start, _)| *start);
+            let records: Vec<VNode> = records.into_iter().map(|(_, record)| record).collect();
+            let provider = to_provider(&data.provider);
+            let nights = data.nights.clone();
+            let chart = self.to_chart(data);
             if records.is_empty() {
                 html! {
                     <div class="records">
+                        <Nights nights={nights} />
+                        {chart}
                         <div class="record">
                             <span class="temperature">{"Looks like it's going to be warm the next few days."}</span>
                         </div>
+                        {provider}
                     </div>
                 }
             } else {
                 html! {
                     <div class="records">
+                        <Nights nights={nights} />
+                        {chart}
                         { records }
+                        {provider}
                     </div>
                 }
             }
@@ -57,8 +86,57 @@ impl Component for Frost {
     }
 }
 
+impl Frost {
+    fn to_chart(&self, data: &BackendResponse) -> VNode {
+        if let Some(forecast) = &self.props.forecast {
+            let hours = forecast.hours.clone();
+            let thresholds = self.props.thresholds;
+            let cold_phases = data.cold_phases.clone();
+            // the phases tell which level the response was analyzed at
+            let level = data
+                .cold_phases
+                .first()
+                .map_or(self.props.level, |phase| phase.level);
+            let local_times = data
+                .nights
+                .iter()
+                .map(|night| night.min_temp_at)
+                .chain(data.cold_phases.iter().flat_map(|p| vec![p.start, p.end]))
+                .chain(data.heat_phases.iter().flat_map(|p| vec![p.start, p.end]))
+                .collect();
+            html! {
+                <Chart hours={hours} thresholds={thresholds} cold_phases={cold_phases} level={level} local_times={local_times} />
+            }
+        } else {
+            html! {}
+        }
+    }
+}
+
 fn to_record(phase: &ColdPhase) -> VNode {
     html! {
         <Record phase={phase} />
     }
 }
+
+fn to_heat_record(phase: &HeatPhase) -> VNode {
+    html! {
+        <HeatRecord phase={phase} />
+    }
+}
+
+fn to_hazard(hazard: &IceHazard) -> VNode {
+    html! {
+        <Hazard hazard={hazard} />
+    }
+}
+
+fn to_provider(provider: &Option<String>) -> VNode {
+    if let Some(provider) = provider {
+        html! {
+            <div class="provider">{"Weather data provided by "}{provider}</div>
+        }
+    } else {
+        html! {}
+    }
+}
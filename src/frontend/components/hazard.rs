@@ -0,0 +1,66 @@
+use crate::common::IceHazard;
+use yew::prelude::*;
+
+#[derive(Debug, Clone, Properties, PartialEq)]
+pub struct Props {
+    pub hazard: IceHazard,
+}
+
+pub struct Hazard {
+    props: Props,
+}
+
+impl Component for Hazard {
+    type Message = ();
+    type Properties = Props;
+
+    fn create(props: Self::Properties, _link: ComponentLink<Self>) -> Self {
+        Hazard { props }
+    }
+
+    fn update(&mut self, _msg: Self::Message) -> ShouldRender {
+        false
+    }
+
+    fn change(&mut self, _props: Self::Properties) -> ShouldRender {
+        false
+    }
+
+    fn view(&self) -> Html {
+        let hazard = &self.props.hazard;
+
+        let date_start = hazard.start.format("%Y-%m-%d");
+        let type_text = hazard.kind.to_string().to_uppercase();
+        let explanation = if hazard.precipitation > 0.0 {
+            format!("{:.1} mm precipitation", hazard.precipitation)
+        } else {
+            "wet surfaces freezing".to_owned()
+        };
+        let temp = format!(
+            "Slippery roads, temperature as low as {} °C",
+            hazard.min_temp
+        );
+        let timestamp = format!(
+            "between {} and {}",
+            hazard.start.format("%H:%M"),
+            hazard.end.format("%H:%M")
+        );
+
+        let date2 = if hazard.start.date() != hazard.end.date() {
+            let date_end = hazard.end.format("%Y-%m-%d");
+            html! {<span class="date2">{"- "}{date_end}</span>}
+        } else {
+            html! {}
+        };
+
+        html! {
+            <div class="record ice-record">
+                <span class="date">{date_start}</span>
+                {date2}
+                <span class="ice">{type_text}{": "}{explanation}</span>
+                <span class="temperature">{temp}</span>
+                <span class="time">{timestamp}</span>
+            </div>
+        }
+    }
+}
@@ -13,8 +13,12 @@ pub struct Header {
 pub struct Props {
     pub location: Option<String>,
     pub notifications_on: bool,
+    pub notifications_supported: bool,
     pub app_link: ComponentLink<FrostApp>,
     pub geolocation_supported: bool,
+    pub saved_places: Vec<SavedPlace>,
+    pub saved_place_index: Option<usize>,
+    pub can_save_place: bool,
 }
 
 impl Component for Header {
@@ -39,7 +43,7 @@ impl Component for Header {
         let location = self.props.location.clone();
         let app_link = self.props.app_link.clone();
         let notifications_icon = if self.props.notifications_on {
-            "fas fa-sync-alt"
+            "fas fa-bell"
         } else {
             "fas fa-bell-slash"
         };
@@ -49,16 +53,77 @@ impl Component for Header {
             frontend::Msg::LocationUpdate(LocationStatus::RequestDeviceLocation)
         });
         let refresh = self.props.app_link.callback(|_| frontend::Msg::Refresh);
+        let notifications_not_supported = !self.props.notifications_supported;
+        let toggle_notifications = self
+            .props
+            .app_link
+            .callback(|_| frontend::Msg::TogglePushAlerts);
+        let place_switcher = self.place_switcher();
+        let saved_place_button = match self.props.saved_place_index {
+            Some(index) => {
+                let remove = self
+                    .props
+                    .app_link
+                    .callback(move |_| frontend::Msg::RemoveSavedPlace(index));
+                html! {
+                    <button title="Remove from saved places" onclick={remove}><i class="fas fa-star"></i></button>
+                }
+            }
+            None => {
+                let save = self.props.app_link.callback(|_| frontend::Msg::SavePlace);
+                let cannot_save = !self.props.can_save_place;
+                html! {
+                    <button title="Save place" disabled={cannot_save} onclick={save}><i class="far fa-star"></i></button>
+                }
+            }
+        };
 
         html! {
             <div class="header">
                 <PlacePicker location={location} app_link={app_link} />
                 <button disabled={geolocation_not_supported} onclick={get_location}><i class="fas fa-map-marker-alt"></i></button>
+                {saved_place_button}
+                {place_switcher}
                 <div class="space"></div>
-                <button disabled=true><i class={notifications_icon}></i></button>
+                <button disabled={notifications_not_supported} onclick={toggle_notifications}><i class={notifications_icon}></i></button>
                 <button onclick={refresh}><i class="fas fa-sync-alt"></i></button>
                 <img class="app-icon" src="/icon.png" alt="frost icon" />
             </div>
         }
     }
 }
+
+impl Header {
+    fn place_switcher(&self) -> Html {
+        if self.props.saved_places.is_empty() {
+            return html! {};
+        }
+
+        let on_change = self.props.app_link.callback(|e: ChangeData| match e {
+            ChangeData::Select(select) => match select.value().parse() {
+                Ok(index) => frontend::Msg::SelectSavedPlace(index),
+                Err(_) => frontend::Msg::Refresh,
+            },
+            _ => frontend::Msg::Refresh,
+        });
+        let selected = self.props.saved_place_index;
+        let places: Vec<Html> = self
+            .props
+            .saved_places
+            .iter()
+            .enumerate()
+            .map(|(index, saved)| {
+                html! {
+                    <option value={index} selected={selected == Some(index)}>{&saved.place.name}</option>
+                }
+            })
+            .collect();
+
+        html! {
+            <select class="place-switcher" onchange={on_change}>
+                <option value="" disabled=true selected={selected.is_none()}>{"Saved places"}</option>
+                { places }
+            </select>
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use crate::common::{HeatPhase, RecordType};
+use yew::prelude::*;
+
+#[derive(Debug, Clone, Properties, PartialEq)]
+pub struct Props {
+    pub phase: HeatPhase,
+}
+
+pub struct HeatRecord {
+    props: Props,
+}
+
+impl Component for HeatRecord {
+    type Message = ();
+    type Properties = Props;
+
+    fn create(props: Self::Properties, _link: ComponentLink<Self>) -> Self {
+        HeatRecord { props }
+    }
+
+    fn update(&mut self, _msg: Self::Message) -> ShouldRender {
+        false
+    }
+
+    fn change(&mut self, _props: Self::Properties) -> ShouldRender {
+        false
+    }
+
+    fn view(&self) -> Html {
+        let phase = &self.props.phase;
+
+        let date_start = phase.start.format("%Y-%m-%d");
+        let type_text = format!("HEAT {}", phase.record_type.to_string().to_uppercase());
+        let explanation = match phase.record_type {
+            RecordType::Danger => format!("above {} °C", phase.danger_threshold),
+            _ => format!("above {} °C", phase.warning_threshold),
+        };
+        let temp = format!("Temperature as high as {} °C", phase.max_temp);
+        let timestamp = format!(
+            "between {} and {}",
+            phase.start.format("%H:%M"),
+            phase.end.format("%H:%M")
+        );
+
+        let date2 = if phase.start.date() != phase.end.date() {
+            let date_end = phase.end.format("%Y-%m-%d");
+            html! {<span class="date2">{"- "}{date_end}</span>}
+        } else {
+            html! {}
+        };
+
+        html! {
+            <div class="record heat-record">
+                <span class="date">{date_start}</span>
+                {date2}
+                <span class="heat">{type_text}{": "}{explanation}</span>
+                <span class="temperature">{temp}</span>
+                <span class="time">{timestamp}</span>
+            </div>
+        }
+    }
+}
@@ -1,5 +1,10 @@
+pub mod chart;
 pub mod frost;
+pub mod hazard;
 pub mod header;
+pub mod heat;
+pub mod nights;
 pub mod place_picker;
 pub mod record;
+pub mod settings;
 pub mod status;
@@ -0,0 +1,60 @@
+use crate::common::NightSummary;
+use yew::prelude::*;
+use yew::virtual_dom::VNode;
+
+#[derive(Debug, Clone, Properties, PartialEq)]
+pub struct Props {
+    pub nights: Vec<NightSummary>,
+}
+
+pub struct Nights {
+    props: Props,
+}
+
+impl Component for Nights {
+    type Message = ();
+    type Properties = Props;
+
+    fn create(props: Self::Properties, _link: ComponentLink<Self>) -> Self {
+        Nights { props }
+    }
+
+    fn update(&mut self, _msg: Self::Message) -> ShouldRender {
+        false
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.props = props;
+        true
+    }
+
+    fn view(&self) -> Html {
+        if self.props.nights.is_empty() {
+            return html! {};
+        }
+
+        let nights: Vec<VNode> = self.props.nights.iter().map(to_night).collect();
+
+        html! {
+            <div class="nights">
+                { nights }
+            </div>
+        }
+    }
+}
+
+fn to_night(night: &NightSummary) -> VNode {
+    let class = match &night.record_type {
+        Some(record_type) => format!("night night-{}", record_type.to_string().replace(' ', "-")),
+        None => "night".to_owned(),
+    };
+
+    html! {
+        <div class={class}>
+            <span class="night-date">{night.date.format("%a %d")}</span>
+            <span class="night-min">{format!("{:.1} °C", night.min_temp)}</span>
+            <span class="night-time">{"at "}{night.min_temp_at.format("%H:%M")}</span>
+            <span class="night-max">{format!("max {:.1} °C", night.max_temp)}</span>
+        </div>
+    }
+}
@@ -1,4 +1,4 @@
-use crate::common::ColdPhase;
+use crate::common::{ColdPhase, RecordType, TemperatureLevel, Trigger};
 use chrono::prelude::*;
 use yew::prelude::*;
 
@@ -32,13 +32,31 @@ impl Component for Record {
 
         // let location = &phase.location;
         let date_start = phase.start.format("%Y-%m-%d");
-        let class = phase.record_type.to_string();
-        let type_text = class.to_uppercase();
-        let explanation = match phase.record_type {
-            crate::common::RecordType::Warning => format!("< {} °C", phase.warning_threshold),
-            crate::common::RecordType::Danger => format!("< {} °C", phase.danger_threshold),
+        let class = phase.record_type.to_string().replace(' ', "-");
+        let type_text = phase.record_type.to_string().to_uppercase();
+        let explanation = match (&phase.record_type, &phase.frost_risk) {
+            (RecordType::Warning, _) => format!("< {} °C", phase.warning_threshold),
+            (RecordType::GroundFrost, Some(risk)) => format!("hoar frost likely, {}", risk),
+            (RecordType::GroundFrost, None) => "hoar frost likely".to_owned(),
+            (RecordType::Danger, _) => format!("< {} °C", phase.danger_threshold),
+        };
+        let explanation = match &phase.preset {
+            Some(preset) => format!("{} for {}", explanation, preset),
+            None => explanation,
+        };
+        let temp = match (phase.trigger, phase.level) {
+            (Trigger::WindChill, _) => format!(
+                "Wind chill makes it feel as cold as {:.1} °C",
+                phase.min_temp
+            ),
+            (Trigger::Temperature, TemperatureLevel::Air) => {
+                format!("Temperature drops as low as {} °C", phase.min_temp)
+            }
+            (Trigger::Temperature, TemperatureLevel::Ground) => format!(
+                "Ground temperature drops as low as {:.1} °C",
+                phase.min_temp
+            ),
         };
-        let temp = format!("Temperature drops as low as {} °C", phase.min_temp);
         let timestamp = format_time(&phase);
 
         let date2 = if phase.start.date() != phase.end.date() {
@@ -62,17 +80,19 @@ impl Component for Record {
 }
 
 fn format_time(phase: &ColdPhase) -> String {
-    if phase.end.day() - phase.start.day() < 2 {
-        format!(
-            "between {} and {}",
-            phase.start.format("%H:%M"),
-            phase.end.format("%H:%M")
-        )
+    let format = if phase.end.day() - phase.start.day() < 2 {
+        "%H:%M"
+    } else {
+        "%Y-%m-%d %H:%M"
+    };
+    let between = format!(
+        "between {} and {}",
+        phase.start.format(format),
+        phase.end.format(format)
+    );
+    if let Some(min_temp_at) = phase.min_temp_at {
+        format!("{}, coldest at {}", between, min_temp_at.format(format))
     } else {
-        format!(
-            "between {} and {}",
-            phase.start.format("%Y-%m-%d %H:%M"),
-            phase.end.format("%Y-%m-%d %H:%M")
-        )
+        between
     }
 }
@@ -0,0 +1,277 @@
+use crate::common::*;
+use crate::frontend;
+use crate::frontend::FrostApp;
+use yew::prelude::*;
+
+#[derive(Debug)]
+pub struct Settings {
+    link: ComponentLink<Self>,
+    props: Props,
+    warning_threshold: String,
+    danger_threshold: String,
+    heat_warning_threshold: String,
+    heat_danger_threshold: String,
+    horizon: String,
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone, Properties)]
+pub struct Props {
+    pub thresholds: Thresholds,
+    pub heat_thresholds: Option<Thresholds>,
+    pub wind_chill: bool,
+    pub horizon: Option<u32>,
+    pub max_horizon: Option<u32>,
+    pub preset: Option<String>,
+    pub app_link: ComponentLink<FrostApp>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Msg {
+    WarningThresholdInput(String),
+    DangerThresholdInput(String),
+    HeatWarningThresholdInput(String),
+    HeatDangerThresholdInput(String),
+    HorizonInput(String),
+    PresetSelected(String),
+    WindChillToggled,
+    Save,
+}
+
+impl Component for Settings {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        Settings {
+            link,
+            warning_threshold: props.thresholds.0.to_string(),
+            danger_threshold: props.thresholds.1.to_string(),
+            heat_warning_threshold: heat_threshold_input(props.heat_thresholds, |t| t.0),
+            heat_danger_threshold: heat_threshold_input(props.heat_thresholds, |t| t.1),
+            horizon: horizon_input(props.horizon),
+            props,
+            error: None,
+        }
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        match msg {
+            Msg::WarningThresholdInput(value) => self.warning_threshold = value,
+            Msg::DangerThresholdInput(value) => self.danger_threshold = value,
+            Msg::HeatWarningThresholdInput(value) => self.heat_warning_threshold = value,
+            Msg::HeatDangerThresholdInput(value) => self.heat_danger_threshold = value,
+            Msg::HorizonInput(value) => self.horizon = value,
+            Msg::PresetSelected(id) => {
+                let preset = if id.is_empty() { None } else { Some(id) };
+                self.props
+                    .app_link
+                    .send_message(frontend::Msg::PresetUpdate(preset));
+            }
+            Msg::WindChillToggled => {
+                let wind_chill = !self.props.wind_chill;
+                self.props
+                    .app_link
+                    .send_message(frontend::Msg::WindChillUpdate(wind_chill));
+            }
+            Msg::Save => match self.parse() {
+                Ok((thresholds, heat_thresholds, horizon)) => {
+                    self.error = None;
+                    self.horizon = horizon_input(horizon);
+                    // Only send what changed, so that saving heat thresholds keeps the preset
+                    if thresholds != self.props.thresholds {
+                        self.props
+                            .app_link
+                            .send_message(frontend::Msg::ThresholdsUpdate(thresholds));
+                    }
+                    if heat_thresholds != self.props.heat_thresholds {
+                        self.props
+                            .app_link
+                            .send_message(frontend::Msg::HeatThresholdsUpdate(heat_thresholds));
+                    }
+                    if horizon != self.props.horizon {
+                        self.props
+                            .app_link
+                            .send_message(frontend::Msg::HorizonUpdate(horizon));
+                    }
+                }
+                Err(e) => self.error = Some(e),
+            },
+        }
+        true
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        if self.props.thresholds != props.thresholds {
+            self.warning_threshold = props.thresholds.0.to_string();
+            self.danger_threshold = props.thresholds.1.to_string();
+        }
+        if self.props.heat_thresholds != props.heat_thresholds {
+            self.heat_warning_threshold = heat_threshold_input(props.heat_thresholds, |t| t.0);
+            self.heat_danger_threshold = heat_threshold_input(props.heat_thresholds, |t| t.1);
+        }
+        if self.props.horizon != props.horizon {
+            self.horizon = horizon_input(props.horizon);
+        }
+        self.props = props;
+        true
+    }
+
+    fn view(&self) -> Html {
+        let on_warning_input = self
+            .link
+            .callback(|e: InputData| Msg::WarningThresholdInput(e.value));
+        let on_danger_input = self
+            .link
+            .callback(|e: InputData| Msg::DangerThresholdInput(e.value));
+        let on_heat_warning_input = self
+            .link
+            .callback(|e: InputData| Msg::HeatWarningThresholdInput(e.value));
+        let on_heat_danger_input = self
+            .link
+            .callback(|e: InputData| Msg::HeatDangerThresholdInput(e.value));
+        let on_horizon_input = self
+            .link
+            .callback(|e: InputData| Msg::HorizonInput(e.value));
+        let max_horizon = self
+            .props
+            .max_horizon
+            .map_or_else(String::new, |max_horizon| max_horizon.to_string());
+        let on_preset_change = self.link.callback(|e: ChangeData| match e {
+            ChangeData::Select(select) => Msg::PresetSelected(select.value()),
+            _ => Msg::PresetSelected(String::new()),
+        });
+        let on_wind_chill_change = self.link.callback(|_: ChangeData| Msg::WindChillToggled);
+        let selected = self.props.preset.as_deref().unwrap_or("");
+        let presets: Vec<Html> = PLANT_PRESETS
+            .iter()
+            .map(|preset| {
+                html! {
+                    <option value={preset.id} selected={preset.id == selected}>{preset.name}</option>
+                }
+            })
+            .collect();
+
+        let on_submit = self.link.callback(|e: FocusEvent| {
+            e.prevent_default();
+            Msg::Save
+        });
+
+        let error = if let Some(error) = &self.error {
+            html! {<span class="settings-error">{error}</span>}
+        } else {
+            html! {}
+        };
+
+        html! {
+            <details class="settings">
+                <summary>{"Settings"}</summary>
+                <form onsubmit={on_submit}>
+                    <label for="preset">{"Plants"}</label>
+                    <select id="preset" onchange={on_preset_change}>
+                        <option value="" selected={selected.is_empty()}>{"custom"}</option>
+                        { presets }
+                    </select>
+                    <label for="warning-threshold">{"Warning threshold (°C)"}</label>
+                    <input type="number" step="0.5" id="warning-threshold" value={&self.warning_threshold} oninput={on_warning_input} />
+                    <label for="danger-threshold">{"Danger threshold (°C)"}</label>
+                    <input type="number" step="0.5" id="danger-threshold" value={&self.danger_threshold} oninput={on_danger_input} />
+                    <label for="heat-warning-threshold">{"Heat warning threshold (°C)"}</label>
+                    <input type="number" step="0.5" id="heat-warning-threshold" placeholder="off" value={&self.heat_warning_threshold} oninput={on_heat_warning_input} />
+                    <label for="heat-danger-threshold">{"Heat danger threshold (°C)"}</label>
+                    <input type="number" step="0.5" id="heat-danger-threshold" placeholder="off" value={&self.heat_danger_threshold} oninput={on_heat_danger_input} />
+                    <label for="horizon">{"Forecast horizon (hours)"}</label>
+                    <input type="number" step="1" min="1" max={max_horizon} id="horizon" placeholder="default" value={&self.horizon} oninput={on_horizon_input} />
+                    <label for="wind-chill">{"Include wind chill"}</label>
+                    <input type="checkbox" id="wind-chill" checked={self.props.wind_chill} onchange={on_wind_chill_change} />
+                    {error}
+                    <button type="submit">{"Save"}</button>
+                </form>
+            </details>
+        }
+    }
+}
+
+impl Settings {
+    fn parse(&self) -> Result<(Thresholds, Option<Thresholds>, Option<u32>), String> {
+        let thresholds = parse_thresholds(&self.warning_threshold, &self.danger_threshold)?;
+        let heat_thresholds =
+            parse_heat_thresholds(&self.heat_warning_threshold, &self.heat_danger_threshold)?;
+        let horizon = parse_horizon(&self.horizon, self.props.max_horizon)?;
+        Ok((thresholds, heat_thresholds, horizon))
+    }
+}
+
+fn horizon_input(horizon: Option<u32>) -> String {
+    horizon.map_or_else(String::new, |horizon| horizon.to_string())
+}
+
+fn heat_threshold_input(
+    heat_thresholds: Option<Thresholds>,
+    pick: fn(Thresholds) -> f32,
+) -> String {
+    heat_thresholds.map_or_else(String::new, |thresholds| pick(thresholds).to_string())
+}
+
+fn parse_threshold(value: &str, name: &str) -> Result<f32, String> {
+    let threshold: f32 = value
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid {} threshold '{}'", name, value))?;
+
+    if !threshold.is_finite() {
+        return Err("Thresholds must be finite numbers".to_owned());
+    }
+
+    Ok(threshold)
+}
+
+fn parse_thresholds(warning_threshold: &str, danger_threshold: &str) -> Result<Thresholds, String> {
+    let warning_threshold = parse_threshold(warning_threshold, "warning")?;
+    let danger_threshold = parse_threshold(danger_threshold, "danger")?;
+
+    if danger_threshold > warning_threshold {
+        return Err("Danger threshold must not be above warning threshold".to_owned());
+    }
+
+    Ok((warning_threshold, danger_threshold))
+}
+
+/// Both heat thresholds empty turns heat warnings off.
+fn parse_heat_thresholds(
+    warning_threshold: &str,
+    danger_threshold: &str,
+) -> Result<Option<Thresholds>, String> {
+    if warning_threshold.trim().is_empty() && danger_threshold.trim().is_empty() {
+        return Ok(None);
+    }
+
+    let warning_threshold = parse_threshold(warning_threshold, "heat warning")?;
+    let danger_threshold = parse_threshold(danger_threshold, "heat danger")?;
+
+    if warning_threshold > danger_threshold {
+        return Err("Heat warning threshold must not be above heat danger threshold".to_owned());
+    }
+
+    Ok(Some((warning_threshold, danger_threshold)))
+}
+
+/// An empty horizon uses the server's default, longer ones are cut to the server's maximum.
+fn parse_horizon(horizon: &str, max_horizon: Option<u32>) -> Result<Option<u32>, String> {
+    if horizon.trim().is_empty() {
+        return Ok(None);
+    }
+
+    let parsed: u32 = horizon
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid horizon '{}'", horizon))?;
+
+    if parsed == 0 {
+        return Err("Horizon must be at least one hour".to_owned());
+    }
+
+    Ok(Some(
+        max_horizon.map_or(parsed, |max_horizon| parsed.min(max_horizon)),
+    ))
+}
@@ -45,6 +45,25 @@ extern "C" {
     fn store_js(key: &str, value: &str) -> Result<(), JsValue>;
     #[wasm_bindgen(catch)]
     fn get_stored_js(key: &str) -> Result<Option<String>, JsValue>;
+    #[wasm_bindgen(catch)]
+    fn remove_stored_js(key: &str) -> Result<(), JsValue>;
+}
+
+#[wasm_bindgen(module = "/js/push.js")]
+extern "C" {
+    #[wasm_bindgen(catch)]
+    fn is_push_supported_js() -> Result<bool, JsValue>;
+    #[wasm_bindgen(catch)]
+    fn subscribe_push_js(
+        public_key: &str,
+        on_subscribed: &Closure<dyn Fn(String)>,
+        on_error: &Closure<dyn Fn(String)>,
+    ) -> Result<(), JsValue>;
+    #[wasm_bindgen(catch)]
+    fn unsubscribe_push_js(
+        on_unsubscribed: &Closure<dyn Fn(String)>,
+        on_error: &Closure<dyn Fn(String)>,
+    ) -> Result<(), JsValue>;
 }
 
 #[wasm_bindgen(module = "/js/places.js")]
@@ -140,6 +159,45 @@ pub fn get_stored(key: &str) -> Option<String> {
     }
 }
 
+#[allow(unused_unsafe)]
+pub fn remove_stored(key: &str) {
+    unsafe {
+        if let Err(e) = remove_stored_js(key) {
+            error!("Error removing value from web storage: {:?}", e);
+        }
+    }
+}
+
+#[allow(unused_unsafe)]
+pub fn is_push_supported() -> bool {
+    unsafe { is_push_supported_js().unwrap_or(false) }
+}
+
+#[allow(unused_unsafe)]
+pub fn subscribe_push(
+    public_key: &str,
+    on_subscribed: &Closure<dyn Fn(String)>,
+    on_error: &Closure<dyn Fn(String)>,
+) {
+    unsafe {
+        if let Err(e) = subscribe_push_js(public_key, on_subscribed, on_error) {
+            error!("Error subscribing to push messages: {:?}", e);
+        }
+    }
+}
+
+#[allow(unused_unsafe)]
+pub fn unsubscribe_push(
+    on_unsubscribed: &Closure<dyn Fn(String)>,
+    on_error: &Closure<dyn Fn(String)>,
+) {
+    unsafe {
+        if let Err(e) = unsubscribe_push_js(on_unsubscribed, on_error) {
+            error!("Error unsubscribing from push messages: {:?}", e);
+        }
+    }
+}
+
 #[allow(unused_unsafe)]
 pub fn init_autocomplete(id: &str, on_selected: &Closure<dyn Fn(String)>) {
     unsafe {
@@ -1,7 +1,10 @@
 use self::components::frost::Frost;
 use self::components::header::Header;
+use self::components::settings::Settings;
 use self::components::status::StatusBar;
 use super::common::*;
+use chrono::prelude::*;
+use std::collections::HashMap;
 use wasm_bindgen::prelude::*;
 use yew::format::Nothing;
 use yew::prelude::*;
@@ -14,22 +17,46 @@ static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 pub mod components;
 pub mod js;
 
+const DEFAULT_THRESHOLDS: Thresholds = (5.0, 0.0);
+
 pub struct FrostApp {
     link: ComponentLink<FrostApp>,
     props: Props,
     on_location_success: Closure<dyn Fn(f32, f32)>,
     on_location_error: Closure<dyn Fn(u16, String)>,
     on_notification_permission: Closure<dyn Fn(JsValue)>,
+    on_push_subscribed: Closure<dyn Fn(String)>,
+    on_push_unsubscribed: Closure<dyn Fn(String)>,
+    on_push_error: Closure<dyn Fn(String)>,
     fetch_task: Option<FetchTask>,
+    forecast_task: Option<FetchTask>,
+    max_horizon_task: Option<FetchTask>,
+    push_task: Option<FetchTask>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Msg {
     WeatherUpdate(WeatherDataStatus),
+    ForecastUpdate(Option<Forecast>),
+    MaxHorizonUpdate(Option<u32>),
     NotificationPermissionUpdate(NotificationPermissionStatus),
     Refresh,
     LocationUpdate(LocationStatus),
     PlaceUpdate(PlaceStatus),
+    ThresholdsUpdate(Thresholds),
+    HeatThresholdsUpdate(Option<Thresholds>),
+    WindChillUpdate(bool),
+    HorizonUpdate(Option<u32>),
+    PresetUpdate(Option<String>),
+    SavePlace,
+    SelectSavedPlace(usize),
+    RemoveSavedPlace(usize),
+    TogglePushAlerts,
+    PushKeyReceived(String),
+    PushSubscribed(String),
+    PushRegister(String),
+    PushUnsubscribed(String),
+    PushAlertsUpdate(Result<Option<i64>, String>),
 }
 
 #[derive(Debug, Clone, Properties, PartialEq)]
@@ -37,11 +64,21 @@ pub struct Props {
     pub location: Option<LocationStatus>,
     pub selected_place: PlaceStatus,
     pub weather: WeatherDataStatus,
+    pub forecast: Option<Forecast>,
     pub status: Option<Status>,
     pub notification_permission: NotificationPermissionStatus,
     pub geolocation_supported: bool,
     pub notifications_supported: bool,
     pub thresholds: Thresholds,
+    pub horizon: Option<u32>,
+    pub max_horizon: Option<u32>,
+    pub level: TemperatureLevel,
+    pub wind_chill: bool,
+    pub heat_thresholds: Option<Thresholds>,
+    pub preset: Option<String>,
+    pub saved_places: Vec<SavedPlace>,
+    pub push_supported: bool,
+    pub push_alerts: bool,
 }
 
 impl Component for FrostApp {
@@ -52,6 +89,9 @@ impl Component for FrostApp {
         let link_success = link.clone();
         let link_error = link.clone();
         let link_notification = link.clone();
+        let link_subscribed = link.clone();
+        let link_unsubscribed = link.clone();
+        let link_push_error = link.clone();
         let on_location_success = Closure::new(move |lat, lon| {
             link_success.send_message(Msg::LocationUpdate(LocationStatus::LocationRetrieved(
                 lat, lon,
@@ -66,6 +106,14 @@ impl Component for FrostApp {
             let permission = permission.into();
             link_notification.send_message(Msg::NotificationPermissionUpdate(permission));
         });
+        let on_push_subscribed = Closure::new(move |subscription| {
+            link_subscribed.send_message(Msg::PushSubscribed(subscription))
+        });
+        let on_push_unsubscribed = Closure::new(move |subscription| {
+            link_unsubscribed.send_message(Msg::PushUnsubscribed(subscription))
+        });
+        let on_push_error =
+            Closure::new(move |msg| link_push_error.send_message(Msg::PushAlertsUpdate(Err(msg))));
 
         let mut app = FrostApp {
             link: link.clone(),
@@ -73,10 +121,17 @@ impl Component for FrostApp {
             on_location_success,
             on_location_error,
             on_notification_permission,
+            on_push_subscribed,
+            on_push_unsubscribed,
+            on_push_error,
             fetch_task: None,
+            forecast_task: None,
+            max_horizon_task: None,
+            push_task: None,
         };
 
         js::request_notification_permission(&app.on_notification_permission);
+        app.fetch_max_horizon();
         app.check_for_weather_update();
 
         app
@@ -142,6 +197,21 @@ impl Component for FrostApp {
 
                 true
             }
+            Msg::ForecastUpdate(forecast) => {
+                self.props.forecast = forecast;
+                true
+            }
+            Msg::MaxHorizonUpdate(max_horizon) => {
+                self.max_horizon_task = None;
+                self.props.max_horizon = max_horizon;
+                match (self.props.horizon, max_horizon) {
+                    (Some(horizon), Some(max_horizon)) if horizon > max_horizon => {
+                        warn!("Stored horizon above maximum: {}", horizon);
+                        self.update(Msg::HorizonUpdate(Some(max_horizon)))
+                    }
+                    _ => max_horizon.is_some(),
+                }
+            }
             Msg::PlaceUpdate(place) => {
                 if let PlaceStatus::PlacePicked(Some(place)) = &place {
                     self.props.location = None;
@@ -160,6 +230,246 @@ impl Component for FrostApp {
                 }
                 true
             }
+            Msg::ThresholdsUpdate(thresholds) => {
+                debug!("Storing thresholds: {:?}", thresholds);
+                self.props.thresholds = thresholds;
+                self.props.preset = None;
+                let json = serde_json::to_string(&thresholds).expect("can't fail");
+                js::store(THRESHOLD_KEY, &json);
+                js::remove_stored(PRESET_KEY);
+                self.check_for_weather_update();
+                true
+            }
+            Msg::HeatThresholdsUpdate(heat_thresholds) => {
+                debug!("Storing heat thresholds: {:?}", heat_thresholds);
+                self.props.heat_thresholds = heat_thresholds;
+                match heat_thresholds {
+                    Some(heat_thresholds) => {
+                        let json = serde_json::to_string(&heat_thresholds).expect("can't fail");
+                        js::store(HEAT_THRESHOLD_KEY, &json);
+                    }
+                    None => js::remove_stored(HEAT_THRESHOLD_KEY),
+                }
+                self.check_for_weather_update();
+                true
+            }
+            Msg::WindChillUpdate(wind_chill) => {
+                debug!("Storing wind chill: {}", wind_chill);
+                self.props.wind_chill = wind_chill;
+                if wind_chill {
+                    js::store(WIND_CHILL_KEY, "true");
+                } else {
+                    js::remove_stored(WIND_CHILL_KEY);
+                }
+                self.check_for_weather_update();
+                true
+            }
+            Msg::HorizonUpdate(horizon) => {
+                let horizon = match (horizon, self.props.max_horizon) {
+                    (Some(horizon), Some(max_horizon)) => Some(horizon.min(max_horizon)),
+                    (horizon, _) => horizon,
+                };
+                debug!("Storing horizon: {:?}", horizon);
+                self.props.horizon = horizon;
+                match horizon {
+                    Some(horizon) => js::store(HORIZON_KEY, &horizon.to_string()),
+                    None => js::remove_stored(HORIZON_KEY),
+                }
+                self.check_for_weather_update();
+                true
+            }
+            Msg::PresetUpdate(id) => {
+                match id.as_deref().map(|id| (id, plant_preset(id))) {
+                    Some((id, Some(preset))) => {
+                        debug!("Selected preset: {}", id);
+                        self.props.thresholds = preset.thresholds;
+                        self.props.level = preset.level;
+                        self.props.preset = Some(id.to_owned());
+                        let json = serde_json::to_string(&preset.thresholds).expect("can't fail");
+                        js::store(THRESHOLD_KEY, &json);
+                        js::store(LEVEL_KEY, &preset.level.to_string());
+                        js::store(PRESET_KEY, id);
+                    }
+                    Some((id, None)) => {
+                        warn!("Unknown preset: {}", id);
+                        return false;
+                    }
+                    None => {
+                        self.props.preset = None;
+                        js::remove_stored(PRESET_KEY);
+                    }
+                }
+                self.check_for_weather_update();
+                true
+            }
+            Msg::SavePlace => {
+                if let Some(place) = self.current_place() {
+                    let saved = SavedPlace {
+                        place,
+                        thresholds: Some(self.props.thresholds),
+                        level: Some(self.props.level),
+                        preset: self.props.preset.clone(),
+                    };
+                    match self.saved_place_index() {
+                        Some(index) => self.props.saved_places[index] = saved,
+                        None => self.props.saved_places.push(saved),
+                    }
+                    self.store_saved_places();
+                }
+                true
+            }
+            Msg::SelectSavedPlace(index) => {
+                if let Some(saved) = self.props.saved_places.get(index).cloned() {
+                    self.props.location = None;
+                    self.props.selected_place = PlaceStatus::PlacePicked(Some(saved.place.clone()));
+                    let json = serde_json::to_string(&saved.place).expect("can't fail");
+                    js::store(LOCATION_KEY, &json);
+                    // never carry over the settings of the previous place, places saved before
+                    // thresholds or levels were stored fall back to their preset or the defaults
+                    let preset = saved.preset.as_deref().and_then(plant_preset);
+                    let thresholds = saved
+                        .thresholds
+                        .or_else(|| preset.map(|preset| preset.thresholds))
+                        .unwrap_or(DEFAULT_THRESHOLDS);
+                    let level = saved
+                        .level
+                        .or_else(|| preset.map(|preset| preset.level))
+                        .unwrap_or_default();
+                    self.props.thresholds = thresholds;
+                    self.props.level = level;
+                    let json = serde_json::to_string(&thresholds).expect("can't fail");
+                    js::store(THRESHOLD_KEY, &json);
+                    js::store(LEVEL_KEY, &level.to_string());
+                    match preset {
+                        Some(preset) => {
+                            self.props.preset = Some(preset.id.to_owned());
+                            js::store(PRESET_KEY, preset.id);
+                        }
+                        None => {
+                            self.props.preset = None;
+                            js::remove_stored(PRESET_KEY);
+                        }
+                    }
+                    self.check_for_weather_update();
+                }
+                true
+            }
+            Msg::RemoveSavedPlace(index) => {
+                if index < self.props.saved_places.len() {
+                    self.props.saved_places.remove(index);
+                    self.store_saved_places();
+                }
+                true
+            }
+            Msg::TogglePushAlerts => {
+                if self.props.push_alerts {
+                    js::unsubscribe_push(&self.on_push_unsubscribed, &self.on_push_error);
+                } else {
+                    let callback = |response: Response<Result<String, anyhow::Error>>| {
+                        match parse_backend_response::<String>(response) {
+                            Ok(key) => Msg::PushKeyReceived(key),
+                            Err(e) => Msg::PushAlertsUpdate(Err(e)),
+                        }
+                    };
+                    let request = Request::get("/push/key").body(Nothing);
+                    self.send_push_request(request, callback);
+                }
+                false
+            }
+            Msg::PushKeyReceived(key) => {
+                js::subscribe_push(&key, &self.on_push_subscribed, &self.on_push_error);
+                false
+            }
+            Msg::PushSubscribed(json) => {
+                let subscription = match serde_json::from_str(&json) {
+                    Ok(subscription) => subscription,
+                    Err(e) => {
+                        self.link
+                            .send_message(Msg::PushAlertsUpdate(Err(e.to_string())));
+                        return false;
+                    }
+                };
+                let place = match self.current_place() {
+                    Some(place) => place,
+                    None => {
+                        self.link.send_message(Msg::PushAlertsUpdate(Err(
+                            "Please select a location first!".to_owned(),
+                        )));
+                        return false;
+                    }
+                };
+                let location = place.geometry.as_ref().map(|g| g.location.clone());
+                let location = location.expect("must be set when stored in props");
+                let alert_subscription = AlertSubscription {
+                    id: None,
+                    channel: AlertChannel::Push(subscription),
+                    place_name: place.name,
+                    location: BatchEntry {
+                        lat: location.lat,
+                        lon: location.lng,
+                        thresholds: Some(self.props.thresholds),
+                        preset: self.props.preset.clone(),
+                        level: Some(self.props.level),
+                        horizon: self.props.horizon,
+                    },
+                    quiet_hours: None,
+                    last_alert: None,
+                };
+                let body = serde_json::to_string(&alert_subscription).expect("can't fail");
+                // The endpoint may still be subscribed if the stored flag got lost, so replace it
+                let callback = move |response: Response<Result<String, anyhow::Error>>| {
+                    match parse_backend_response::<bool>(response) {
+                        Ok(_) => Msg::PushRegister(body.clone()),
+                        Err(e) => Msg::PushAlertsUpdate(Err(e)),
+                    }
+                };
+                let request = Request::post("/push/unsubscribe")
+                    .header("Content-Type", "application/json")
+                    .body(Ok::<String, anyhow::Error>(json));
+                self.send_push_request(request, callback);
+                false
+            }
+            Msg::PushRegister(body) => {
+                let callback = |response: Response<Result<String, anyhow::Error>>| {
+                    Msg::PushAlertsUpdate(parse_backend_response::<i64>(response).map(Some))
+                };
+                let request = Request::post("/subscriptions")
+                    .header("Content-Type", "application/json")
+                    .body(Ok::<String, anyhow::Error>(body));
+                self.send_push_request(request, callback);
+                false
+            }
+            Msg::PushUnsubscribed(json) => {
+                if json.is_empty() {
+                    self.link.send_message(Msg::PushAlertsUpdate(Ok(None)));
+                } else {
+                    let callback = |response: Response<Result<String, anyhow::Error>>| {
+                        Msg::PushAlertsUpdate(
+                            parse_backend_response::<bool>(response).map(|_| None),
+                        )
+                    };
+                    let request = Request::post("/push/unsubscribe")
+                        .header("Content-Type", "application/json")
+                        .body(Ok::<String, anyhow::Error>(json));
+                    self.send_push_request(request, callback);
+                }
+                false
+            }
+            Msg::PushAlertsUpdate(Ok(subscription_id)) => {
+                self.props.push_alerts = subscription_id.is_some();
+                match subscription_id {
+                    Some(id) => js::store(PUSH_ALERTS_KEY, &id.to_string()),
+                    None => js::remove_stored(PUSH_ALERTS_KEY),
+                }
+                true
+            }
+            Msg::PushAlertsUpdate(Err(msg)) => {
+                self.props.status = Some(Status::Error {
+                    title: "Error setting up alerts:".to_owned(),
+                    body: msg,
+                });
+                true
+            }
             Msg::Refresh => {
                 self.props.location = None;
                 self.check_for_weather_update();
@@ -179,6 +489,8 @@ impl Component for FrostApp {
     fn view(&self) -> Html {
         let geolocation_supported = self.props.geolocation_supported;
         let weather = self.props.weather.clone();
+        let forecast = self.props.forecast.clone();
+        let thresholds = self.props.thresholds;
         let status = self.props.status.clone();
 
         let location = if let (
@@ -193,10 +505,14 @@ impl Component for FrostApp {
             None
         };
         let app_link = self.link.clone();
+        let saved_places = self.props.saved_places.clone();
+        let saved_place_index = self.saved_place_index();
+        let can_save_place = self.current_place().is_some();
         html! {
             <div class="app">
-                <Header location={location} app_link={app_link} notifications_on={false} geolocation_supported={geolocation_supported} />
-                <Frost weather={weather} />
+                <Header location={location} app_link={app_link} notifications_on={self.props.push_alerts} notifications_supported={self.props.push_supported} geolocation_supported={geolocation_supported} saved_places={saved_places} saved_place_index={saved_place_index} can_save_place={can_save_place} />
+                <Settings thresholds={thresholds} heat_thresholds={self.props.heat_thresholds} wind_chill={self.props.wind_chill} horizon={self.props.horizon} max_horizon={self.props.max_horizon} preset={self.props.preset.clone()} app_link={self.link.clone()} />
+                <Frost weather={weather} forecast={forecast} thresholds={thresholds} level={self.props.level} />
                 <div class="footer">
                     <StatusBar status={status} />
                 </div>
@@ -206,33 +522,86 @@ impl Component for FrostApp {
 }
 
 impl FrostApp {
-    fn try_send_weather_notification(&self, data: &BackendResponse) {
-        if data.cold_phases.is_empty() {
-            return;
+    /// The place weather is currently shown for, either picked or from the device location.
+    fn current_place(&self) -> Option<Place> {
+        match (&self.props.location, &self.props.selected_place) {
+            (Some(LocationStatus::LocationRetrieved(lat, lon)), _) => {
+                let name = match &self.props.weather {
+                    WeatherDataStatus::WeatherDataRetrieved(Ok(data)) => data.location.clone(),
+                    _ => None,
+                };
+                let name = name.unwrap_or_else(|| format!("{:.2}, {:.2}", lat, lon));
+                Some(Place::new(name, *lat, *lon))
+            }
+            (_, PlaceStatus::PlacePicked(Some(place))) => Some(place.clone()),
+            _ => None,
         }
+    }
 
-        if self.props.notification_permission == NotificationPermissionStatus::Granted {
-            self.send_weather_notification(&data.cold_phases);
+    fn send_push_request<B, F>(&mut self, request: Result<Request<B>, http::Error>, callback: F)
+    where
+        B: Into<Result<String, anyhow::Error>>,
+        F: Fn(Response<Result<String, anyhow::Error>>) -> Msg + 'static,
+    {
+        let callback = self.link.callback(callback);
+        let task = request
+            .map_err(|e| e.to_string())
+            .and_then(|request| FetchService::fetch(request, callback).map_err(|e| e.to_string()));
+        match task {
+            Ok(task) => self.push_task = Some(task),
+            Err(e) => self.link.send_message(Msg::PushAlertsUpdate(Err(e))),
         }
     }
 
-    fn send_weather_notification(&self, data: &Vec<ColdPhase>) {
-        let record_type = if data
+    fn saved_place_index(&self) -> Option<usize> {
+        let place = self.current_place()?;
+        self.props
+            .saved_places
             .iter()
-            .find(|p| p.record_type == RecordType::Danger)
-            .is_some()
-        {
-            RecordType::Danger
-        } else {
-            RecordType::Warning
+            .position(|saved| saved.is_at(&place))
+    }
+
+    fn store_saved_places(&self) {
+        let json = serde_json::to_string(&self.props.saved_places).expect("can't fail");
+        js::store(PLACES_KEY, &json);
+    }
+
+    /// Notifies about cold phases that were not announced for this place before, and about
+    /// announced ones that changed or disappeared.
+    fn try_send_weather_notification(&self, data: &BackendResponse) {
+        if self.props.notification_permission != NotificationPermissionStatus::Granted {
+            return;
+        }
+        let place = match self.current_place() {
+            Some(place) => place,
+            None => return,
         };
-        let temp_min = data
-            .iter()
-            .map(|p| p.min_temp)
-            .fold(9000f32, |a, b| a.min(b));
-        let titel = record_type.to_string().to_uppercase();
-        let text = format!("Temperatures as low as {} °C predicted.", temp_min);
-        js::show_notification(&titel, &text, Some("/icon.png"), Some("frost"));
+        let location = place.geometry.as_ref().map(|g| g.location.clone());
+        let location = location.expect("must be set when stored in props");
+        let key = format!("{:.2},{:.2}", location.lat, location.lng);
+        let place_name = data.location.clone().unwrap_or(place.name);
+
+        let mut announced: HashMap<String, Vec<AnnouncedPhase>> = js::get_stored(ANNOUNCED_KEY)
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        let previous = announced.remove(&key).unwrap_or_default();
+        let (announcements, phases) = announcements(&previous, &data.cold_phases, Utc::now());
+
+        for announcement in announcements {
+            let alert = announcement.alert(&place_name);
+            js::show_notification(
+                &alert.title,
+                &alert.body,
+                Some("/icon.png"),
+                Some(&alert.tag),
+            );
+        }
+
+        if !phases.is_empty() {
+            announced.insert(key, phases);
+        }
+        let json = serde_json::to_string(&announced).expect("can't fail");
+        js::store(ANNOUNCED_KEY, &json);
     }
 
     fn check_for_weather_update(self: &mut FrostApp) {
@@ -266,9 +635,68 @@ impl FrostApp {
                         )))
                 }
             }
+            match self.fetch_forecast(lat, lon) {
+                Ok(forecast_task) => self.forecast_task = Some(forecast_task),
+                Err(e) => warn!("Could not request hourly forecast: {}", e),
+            }
+        }
+    }
+
+    fn fetch_max_horizon(&mut self) {
+        let callback = |response: Response<Result<String, anyhow::Error>>| {
+            match parse_backend_response::<u32>(response) {
+                Ok(max_horizon) => Msg::MaxHorizonUpdate(Some(max_horizon)),
+                Err(e) => {
+                    warn!("Could not get maximum forecast horizon: {}", e);
+                    Msg::MaxHorizonUpdate(None)
+                }
+            }
+        };
+        let callback = self.link.callback(callback);
+        let task = Request::get("/forecast/max_horizon")
+            .body(Nothing)
+            .map_err(|e| e.to_string())
+            .and_then(|request| FetchService::fetch(request, callback).map_err(|e| e.to_string()));
+        match task {
+            Ok(task) => self.max_horizon_task = Some(task),
+            Err(e) => warn!("Could not request maximum forecast horizon: {}", e),
         }
     }
 
+    fn fetch_forecast(&self, lat: f32, lon: f32) -> Result<FetchTask, BackendError> {
+        let callback = move |response: Response<Result<String, anyhow::Error>>| {
+            let forecast = match response.body() {
+                Ok(data) => match serde_json::from_str::<ForecastResult>(&data) {
+                    Ok(Ok(forecast)) => Some(forecast),
+                    Ok(Err(e)) => {
+                        warn!("Backend could not provide hourly forecast: {}", e);
+                        None
+                    }
+                    Err(e) => {
+                        warn!("Received invalid hourly forecast: {}", e);
+                        None
+                    }
+                },
+                Err(e) => {
+                    warn!("Error fetching hourly forecast: {}", e);
+                    None
+                }
+            };
+            Msg::ForecastUpdate(forecast)
+        };
+
+        let callback = self.link.callback(callback);
+
+        let mut uri = format!("/forecast?lat={}&lon={}", lat, lon);
+        if let Some(horizon) = self.props.horizon {
+            uri.push_str(&format!("&horizon={}", horizon));
+        }
+        debug!("Requesting hourly forecast from backend...");
+        let request = Request::get(&uri).body(Nothing)?;
+        let fetch_task = convert_err(FetchService::fetch(request, callback));
+        Ok(fetch_task?)
+    }
+
     fn fetch_weather_data(&self, lat: f32, lon: f32) -> Result<FetchTask, BackendError> {
         let callback = move |response: Response<Result<String, anyhow::Error>>| {
             let data = response.body();
@@ -290,10 +718,25 @@ impl FrostApp {
         let warning_threshold = self.props.thresholds.0;
         let danger_threshold = self.props.thresholds.1;
 
-        let uri = format!(
-            "/weather?lat={}&lon={}&warning_threshold={}&danger_threshold={}",
-            lat, lon, warning_threshold, danger_threshold
+        let mut uri = format!(
+            "/weather?lat={}&lon={}&warning_threshold={}&danger_threshold={}&level={}",
+            lat, lon, warning_threshold, danger_threshold, self.props.level
         );
+        if let Some(horizon) = self.props.horizon {
+            uri.push_str(&format!("&horizon={}", horizon));
+        }
+        if self.props.wind_chill {
+            uri.push_str("&wind_chill=true");
+        }
+        if let Some(preset) = &self.props.preset {
+            uri.push_str(&format!("&preset={}", preset));
+        }
+        if let Some((warning_threshold, danger_threshold)) = self.props.heat_thresholds {
+            uri.push_str(&format!(
+                "&heat_warning_threshold={}&heat_danger_threshold={}",
+                warning_threshold, danger_threshold
+            ));
+        }
         debug!("Requesting weather data from backend...");
         let request = Request::get(&uri).body(Nothing)?;
         let fetch_task = convert_err(FetchService::fetch(request, callback));
@@ -301,6 +744,17 @@ impl FrostApp {
     }
 }
 
+/// Parses a JSON serialized `Result` as returned by the push endpoints of the backend.
+fn parse_backend_response<T: serde::de::DeserializeOwned>(
+    response: Response<Result<String, anyhow::Error>>,
+) -> Result<T, String> {
+    let body = response.into_body().map_err(|e| e.to_string())?;
+    match serde_json::from_str::<Result<T, BackendError>>(&body) {
+        Ok(result) => result.map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
 fn convert_err(
     result: Result<FetchTask, anyhow::Error>,
 ) -> Result<FetchTask, Box<dyn std::error::Error>> {
@@ -358,13 +812,58 @@ pub fn main_js() -> Result<(), JsValue> {
     let thresholds = if let Some(thresholds) = thresholds {
         thresholds
     } else {
-        let thresholds = (5.0, 0.0);
+        let thresholds = DEFAULT_THRESHOLDS;
         let value = serde_json::to_string(&thresholds).expect("can't fail");
         js::store(THRESHOLD_KEY, &value);
         thresholds
     };
 
+    let horizon = js::get_stored(HORIZON_KEY).and_then(|value| match value.parse() {
+        Ok(horizon) => Some(horizon),
+        Err(e) => {
+            warn!("Stored horizon invalid: {}", e);
+            None
+        }
+    });
+
+    let level = js::get_stored(LEVEL_KEY).map_or(TemperatureLevel::default(), |value| {
+        value.parse().unwrap_or_else(|e| {
+            warn!("Stored level invalid: {}", e);
+            TemperatureLevel::default()
+        })
+    });
+
+    let wind_chill = js::get_stored(WIND_CHILL_KEY).map_or(false, |value| value == "true");
+
+    let heat_thresholds =
+        js::get_stored(HEAT_THRESHOLD_KEY).and_then(|value| match serde_json::from_str(&value) {
+            Ok(thresholds) => Some(thresholds),
+            Err(e) => {
+                warn!("Stored heat thresholds invalid: {}", e);
+                None
+            }
+        });
+
+    let preset = js::get_stored(PRESET_KEY).filter(|id| {
+        let known = plant_preset(id).is_some();
+        if !known {
+            warn!("Stored preset unknown: {}", id);
+        }
+        known
+    });
+
+    let saved_places = js::get_stored(PLACES_KEY).map_or(Vec::new(), |value| {
+        serde_json::from_str(&value).unwrap_or_else(|e| {
+            warn!("Stored places invalid: {}", e);
+            Vec::new()
+        })
+    });
+
+    let push_supported = js::is_push_supported();
+    let push_alerts = push_supported && js::get_stored(PUSH_ALERTS_KEY).is_some();
+
     let weather = WeatherDataStatus::WaitingForWeatherData;
+    let forecast = None;
     let notification_permission = NotificationPermissionStatus::Default;
     let status = None;
     let location = None;
@@ -372,12 +871,22 @@ pub fn main_js() -> Result<(), JsValue> {
     let props = Props {
         location,
         weather,
+        forecast,
         status,
         notification_permission,
         geolocation_supported,
         notifications_supported,
         selected_place: PlaceStatus::PlacePicked(place),
         thresholds,
+        horizon,
+        max_horizon: None,
+        level,
+        wind_chill,
+        heat_thresholds,
+        preset,
+        saved_places,
+        push_supported,
+        push_alerts,
     };
 
     App::<FrostApp>::new().mount_to_body_with_props(props);
@@ -6,6 +6,7 @@ extern crate serde_derive;
 #[macro_use]
 extern crate log;
 
+#[cfg(not(target_arch = "wasm32"))]
 pub mod backend;
 pub mod common;
 pub mod frontend;
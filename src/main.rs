@@ -6,16 +6,34 @@ extern crate rocket;
 #[macro_use]
 extern crate log;
 
+mod server;
+
 use chrono::prelude::*;
+use chrono_tz::Tz;
 use dotenv::dotenv;
+use frost::backend::brightsky::{self, BrightSky};
+use frost::backend::cache::{CacheKey, ForecastCache};
+use frost::backend::open_meteo::{self, OpenMeteo};
+use frost::backend::provider::WeatherProvider;
+use frost::backend::time_zone::time_zone_at;
+use frost::backend::window::ForecastWindow;
 use frost::backend::*;
 use frost::common::*;
 use rocket::fairing::AdHoc;
+use rocket::request::LenientForm;
 use rocket::response::content;
 use rocket::response::NamedFile;
 use rocket::State;
+use server::alerts::AlertJob;
+use server::email::{self as mail, validate_address, EmailSender, SmtpConfig, SmtpTls};
+use server::net;
+use server::push::{validate_endpoint, PushSender};
+use server::subscriptions::SubscriptionStore;
+use server::webhook::{WebhookConfig, WebhookSender};
 use std::fmt;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 
 #[derive(Debug)]
 struct CookieError(&'static str);
@@ -36,74 +54,645 @@ fn files(file: PathBuf, root: State<RootDir>) -> Option<NamedFile> {
     NamedFile::open(Path::new(&root.0).join(file)).ok()
 }
 
-#[get("/weather?<lat>&<lon>&<warning_threshold>&<danger_threshold>")]
+#[derive(Debug, FromForm)]
+struct WeatherQuery {
+    lat: f32,
+    lon: f32,
+    warning_threshold: Option<f32>,
+    danger_threshold: Option<f32>,
+    preset: Option<String>,
+    horizon: Option<u32>,
+    start: Option<String>,
+    end: Option<String>,
+    level: Option<String>,
+    wind_chill: Option<bool>,
+    hysteresis: Option<f32>,
+    /// in hours
+    min_duration: Option<u32>,
+    /// in hours
+    max_gap: Option<u32>,
+    heat_warning_threshold: Option<f32>,
+    heat_danger_threshold: Option<f32>,
+}
+
+impl From<BatchEntry> for WeatherQuery {
+    fn from(entry: BatchEntry) -> Self {
+        WeatherQuery {
+            lat: entry.lat,
+            lon: entry.lon,
+            warning_threshold: entry.thresholds.map(|t| t.0),
+            danger_threshold: entry.thresholds.map(|t| t.1),
+            preset: entry.preset,
+            horizon: entry.horizon,
+            start: None,
+            end: None,
+            level: entry.level.map(|level| level.to_string()),
+            wind_chill: None,
+            hysteresis: None,
+            min_duration: None,
+            max_gap: None,
+            heat_warning_threshold: None,
+            heat_danger_threshold: None,
+        }
+    }
+}
+
+#[get("/weather?<query..>")]
 fn weather(
+    query: LenientForm<WeatherQuery>,
+    providers: State<Providers>,
+    cache: State<Arc<ForecastCache>>,
+    max_horizon: State<MaxHorizon>,
+) -> Result<content::Json<String>, Box<dyn std::error::Error>> {
+    let response = analyze(&query, &providers, &cache, max_horizon.0);
+    let json = serde_json::to_string(&response)?;
+
+    Ok(content::Json(json))
+}
+
+#[post("/weather/batch", data = "<body>")]
+fn weather_batch(
+    body: String,
+    providers: State<Providers>,
+    cache: State<Arc<ForecastCache>>,
+    max_horizon: State<MaxHorizon>,
+    max_batch_size: State<MaxBatchSize>,
+) -> Result<content::Json<String>, Box<dyn std::error::Error>> {
+    let response: BatchResult = parse_batch(&body, max_batch_size.0).and_then(|queries| {
+        let providers: &Providers = &providers;
+        let cache: &ForecastCache = &cache;
+        let max_horizon = max_horizon.0;
+
+        // forecasts for different locations are fetched concurrently, identical locations
+        // still share cache entries once the first request completes
+        crossbeam_utils::thread::scope(|scope| {
+            let handles: Vec<_> = queries
+                .iter()
+                .map(|query| scope.spawn(move |_| analyze(query, providers, cache, max_horizon)))
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| {
+                    handle.join().unwrap_or_else(|_| {
+                        Err(BackendError::NetworkError(
+                            "Analyzing location failed unexpectedly".to_owned(),
+                        ))
+                    })
+                })
+                .collect()
+        })
+        .map_err(|_| BackendError::NetworkError("Batch request failed unexpectedly".to_owned()))
+    });
+    let json = serde_json::to_string(&response)?;
+
+    Ok(content::Json(json))
+}
+
+fn parse_batch(body: &str, max_batch_size: usize) -> Result<Vec<WeatherQuery>, BackendError> {
+    let entries: Vec<BatchEntry> = serde_json::from_str(body)
+        .map_err(|e| BackendError::InvalidRequest(format!("Invalid batch request: {}", e)))?;
+
+    if entries.len() > max_batch_size {
+        return Err(BackendError::InvalidRequest(format!(
+            "Batch requests must not contain more than {} entries",
+            max_batch_size
+        )));
+    }
+
+    Ok(entries.into_iter().map(WeatherQuery::from).collect())
+}
+
+fn analyze(
+    query: &WeatherQuery,
+    providers: &Providers,
+    cache: &ForecastCache,
+    max_horizon: chrono::Duration,
+) -> BackendResult {
+    let (lat, lon) = (query.lat, query.lon);
+    let tz = time_zone_at(lat, lon);
+    let now = Utc::now().with_timezone(&tz);
+
+    let window = request_window(
+        now,
+        query.start.as_deref(),
+        query.end.as_deref(),
+        query.horizon,
+        max_horizon,
+    )?;
+    let base = base_options(query)?;
+    let forecast = fetch_window(lat, lon, &window, providers, cache)?;
+    let options = AnalysisOptions {
+        wind_chill: query.wind_chill.unwrap_or(false),
+        hysteresis: query.hysteresis.unwrap_or(0.0),
+        min_duration: chrono::Duration::hours(query.min_duration.unwrap_or(0) as i64),
+        max_gap: chrono::Duration::hours(query.max_gap.unwrap_or(0) as i64),
+        ..base
+    };
+    Ok(accumulate_cold_phases(&options, tz, &forecast))
+}
+
+#[derive(Debug, FromForm)]
+struct ForecastQuery {
     lat: f32,
     lon: f32,
-    warning_threshold: f32,
-    danger_threshold: f32,
-    brightsky_api_endpoint: State<BrightSkyEndpoint>,
+    horizon: Option<u32>,
+    start: Option<String>,
+    end: Option<String>,
+}
+
+#[get("/forecast?<query..>")]
+fn forecast(
+    query: LenientForm<ForecastQuery>,
+    providers: State<Providers>,
+    cache: State<Arc<ForecastCache>>,
+    max_horizon: State<MaxHorizon>,
 ) -> Result<content::Json<String>, Box<dyn std::error::Error>> {
-    let now: DateTime<Local> = Local::now();
-    let noon_in_three_days: DateTime<Local> = (now + chrono::Duration::days(3))
-        .with_hour(12)
-        .and_then(|t| t.with_minute(0))
-        .and_then(|t| t.with_second(0))
-        .expect("always noon, can't be invalid");
-
-    let api_endpoint = &brightsky_api_endpoint.0;
-
-    let url = format!(
-        "{}?lat={}&lon={}&date={}&last_date={}",
-        api_endpoint,
-        lat,
-        lon,
-        now.to_rfc3339(),
-        noon_in_three_days.to_rfc3339()
+    let (lat, lon) = (query.lat, query.lon);
+    let now = Utc::now().with_timezone(&time_zone_at(lat, lon));
+
+    let response: ForecastResult = request_window(
+        now,
+        query.start.as_deref(),
+        query.end.as_deref(),
+        query.horizon,
+        max_horizon.0,
     )
-    // TODO escape forbidden characters in a more robust way!
-    .replace("+", "%2b");
+    .and_then(|window| fetch_window(lat, lon, &window, &providers, &cache));
+    let json = serde_json::to_string(&response)?;
+
+    Ok(content::Json(json))
+}
+
+/// Lets clients limit the horizon they request to what the server accepts.
+#[get("/forecast/max_horizon")]
+fn max_horizon(
+    max_horizon: State<MaxHorizon>,
+) -> Result<content::Json<String>, Box<dyn std::error::Error>> {
+    let response: Result<u32, BackendError> = Ok(max_horizon.0.num_hours() as u32);
+    let json = serde_json::to_string(&response)?;
+
+    Ok(content::Json(json))
+}
+
+/// Returns the hours of the window, using the cached forecast if there is one.
+fn fetch_window(
+    lat: f32,
+    lon: f32,
+    window: &ForecastWindow,
+    providers: &Providers,
+    cache: &ForecastCache,
+) -> Result<Forecast, BackendError> {
+    let key = CacheKey::new(lat, lon, &window.start, &window.end);
+    let forecast = cache.get_or_fetch(key, || {
+        providers.fetch_forecast(
+            lat,
+            lon,
+            window.start.with_timezone(&Utc),
+            window.end.with_timezone(&Utc),
+        )
+    })?;
+    Ok(window.filter(&forecast))
+}
+
+#[get("/push/key")]
+fn push_key(push: State<Push>) -> Result<content::Json<String>, Box<dyn std::error::Error>> {
+    let response: Result<String, BackendError> =
+        push.sender().map(|sender| sender.public_key().to_owned());
+    let json = serde_json::to_string(&response)?;
+
+    Ok(content::Json(json))
+}
+
+#[post("/push/unsubscribe", data = "<body>")]
+fn push_unsubscribe(
+    body: String,
+    store: State<Arc<SubscriptionStore>>,
+) -> Result<content::Json<String>, Box<dyn std::error::Error>> {
+    let response: Result<bool, BackendError> = serde_json::from_str::<PushSubscription>(&body)
+        .map_err(|e| BackendError::InvalidRequest(format!("Invalid subscription: {}", e)))
+        .and_then(|subscription| Ok(store.delete_recipient(&subscription.endpoint)?));
+    let json = serde_json::to_string(&response)?;
+
+    Ok(content::Json(json))
+}
+
+#[post("/subscriptions", data = "<body>")]
+fn create_subscription(
+    body: String,
+    push: State<Push>,
+    email: State<Email>,
+    webhooks: State<Webhooks>,
+    store: State<Arc<SubscriptionStore>>,
+) -> Result<content::Json<String>, Box<dyn std::error::Error>> {
+    let response: Result<i64, BackendError> = parse_subscription(&body, &push, &email, &webhooks)
+        .and_then(|subscription| insert_subscription(&subscription, &store, &email));
+    let json = serde_json::to_string(&response)?;
+
+    Ok(content::Json(json))
+}
 
-    debug!("Pulling weather data from {}", url);
+#[get("/subscriptions/<id>")]
+fn get_subscription(
+    id: i64,
+    store: State<Arc<SubscriptionStore>>,
+) -> Result<content::Json<String>, Box<dyn std::error::Error>> {
+    let response: Result<SubscriptionInfo, BackendError> = store
+        .get(id)
+        .map_err(BackendError::from)
+        .and_then(|subscription| subscription.ok_or_else(|| unknown_subscription(id)))
+        .map(SubscriptionInfo::from);
+    let json = serde_json::to_string(&response)?;
 
-    let body = reqwest::blocking::get(&url)?.text()?;
+    Ok(content::Json(json))
+}
 
-    debug!("Received data:\n{}", body);
+#[put("/subscriptions/<id>", data = "<body>")]
+fn update_subscription(
+    id: i64,
+    body: String,
+    push: State<Push>,
+    email: State<Email>,
+    webhooks: State<Webhooks>,
+    store: State<Arc<SubscriptionStore>>,
+) -> Result<content::Json<String>, Box<dyn std::error::Error>> {
+    let response: Result<(), BackendError> = parse_subscription(&body, &push, &email, &webhooks)
+        .and_then(|subscription| {
+            let existing = store.get(id)?.ok_or_else(|| unknown_subscription(id))?;
+            // a new recipient has to confirm the subscription first
+            if existing.channel.key() != subscription.channel.key() {
+                return Err(BackendError::InvalidRequest(
+                    "The recipient of a subscription can't be changed, create a new one instead"
+                        .to_owned(),
+                ));
+            }
+            if store.update(id, &subscription)? {
+                Ok(())
+            } else {
+                Err(unknown_subscription(id))
+            }
+        });
+    let json = serde_json::to_string(&response)?;
 
-    let response = parse_response(&body, warning_threshold, danger_threshold)?;
+    Ok(content::Json(json))
+}
+
+#[delete("/subscriptions/<id>")]
+fn delete_subscription(
+    id: i64,
+    store: State<Arc<SubscriptionStore>>,
+) -> Result<content::Json<String>, Box<dyn std::error::Error>> {
+    let response: Result<(), BackendError> =
+        store
+            .delete(id)
+            .map_err(BackendError::from)
+            .and_then(|deleted| {
+                if deleted {
+                    Ok(())
+                } else {
+                    Err(unknown_subscription(id))
+                }
+            });
     let json = serde_json::to_string(&response)?;
 
     Ok(content::Json(json))
 }
 
-fn parse_response(
-    brightsky_response: &str,
-    warning_threshold: f32,
-    danger_threshold: f32,
-) -> Result<BackendResult, Box<dyn std::error::Error>> {
-    let data = serde_json::from_str(brightsky_response);
-    match data {
-        Ok(data) => {
-            let cold_phases = accumulate_cold_phases(warning_threshold, danger_threshold, &data);
-            Ok(Ok(cold_phases))
+#[get("/subscriptions/confirm/<token>")]
+fn confirm_subscription(
+    token: String,
+    store: State<Arc<SubscriptionStore>>,
+) -> Result<content::Html<String>, Box<dyn std::error::Error>> {
+    let page = if store.confirm(&token)? {
+        link_page(
+            "Subscription confirmed",
+            "You will receive frost alerts from now on.",
+        )
+    } else {
+        link_page("Unknown subscription", "This link is no longer valid.")
+    };
+
+    Ok(page)
+}
+
+#[get("/subscriptions/unsubscribe/<token>")]
+fn unsubscribe(
+    token: String,
+    store: State<Arc<SubscriptionStore>>,
+) -> Result<content::Html<String>, Box<dyn std::error::Error>> {
+    let page = if store.delete_token(&token)? {
+        link_page(
+            "Unsubscribed",
+            "You will not receive any more frost alerts for this subscription.",
+        )
+    } else {
+        link_page("Unknown subscription", "This link is no longer valid.")
+    };
+
+    Ok(page)
+}
+
+/// Minimal page shown when a link from an email is opened.
+fn link_page(title: &str, text: &str) -> content::Html<String> {
+    content::Html(format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>{0}</title></head>\
+         <body><h1>{0}</h1><p>{1}</p></body></html>",
+        title, text
+    ))
+}
+
+/// Stores a validated subscription and returns its id. Email subscriptions only receive alerts
+/// once the link in the confirmation mail sent here was opened.
+fn insert_subscription(
+    subscription: &AlertSubscription,
+    store: &SubscriptionStore,
+    email: &Email,
+) -> Result<i64, BackendError> {
+    let address = match &subscription.channel {
+        AlertChannel::Email { address } => address,
+        _ => return Ok(store.insert(subscription, true)?.id()),
+    };
+
+    let stored = store.insert(subscription, false)?;
+    let confirmation = mail::confirmation(
+        &subscription.place_name,
+        &mail::confirm_url(&email.public_url, &stored.token),
+        &mail::unsubscribe_url(&email.public_url, &stored.token),
+    );
+    if let Err(e) = email.sender()?.send(address, &confirmation) {
+        store.delete(stored.id())?;
+        return Err(BackendError::NetworkError(format!(
+            "Could not send confirmation email: {}",
+            e
+        )));
+    }
+
+    Ok(stored.id())
+}
+
+/// Parses and validates a subscription, the server-maintained fields are ignored.
+fn parse_subscription(
+    body: &str,
+    push: &Push,
+    email: &Email,
+    webhooks: &Webhooks,
+) -> Result<AlertSubscription, BackendError> {
+    let subscription: AlertSubscription = serde_json::from_str(body)
+        .map_err(|e| BackendError::InvalidRequest(format!("Invalid subscription: {}", e)))?;
+    match &subscription.channel {
+        AlertChannel::Push(subscription) => {
+            push.sender()?;
+            validate_endpoint(&subscription.endpoint).map_err(|e| {
+                BackendError::InvalidRequest(format!("Invalid push endpoint: {}", e))
+            })?;
+        }
+        AlertChannel::Email { address } => {
+            email.sender()?;
+            validate_address(address).map_err(|e| {
+                BackendError::InvalidRequest(format!("Invalid email address: {}", e))
+            })?;
+        }
+        AlertChannel::Webhook { url, secret } => {
+            webhooks
+                .sender()?
+                .validate_url(url)
+                .map_err(|e| BackendError::InvalidRequest(format!("Invalid webhook URL: {}", e)))?;
+            if secret.is_empty() {
+                return Err(BackendError::InvalidRequest(
+                    "Webhooks need a secret to sign requests with".to_owned(),
+                ));
+            }
+        }
+    }
+    if let Some(quiet_hours) = subscription.quiet_hours {
+        if quiet_hours.start > 23 || quiet_hours.end > 23 {
+            return Err(BackendError::InvalidRequest(
+                "Quiet hours must be between 0 and 23".to_owned(),
+            ));
+        }
+    }
+    base_options(&WeatherQuery::from(subscription.location.clone()))?;
+
+    Ok(AlertSubscription {
+        id: None,
+        last_alert: None,
+        ..subscription
+    })
+}
+
+fn unknown_subscription(id: i64) -> BackendError {
+    BackendError::InvalidRequest(format!("Unknown subscription {}", id))
+}
+
+fn request_window(
+    now: DateTime<Tz>,
+    start: Option<&str>,
+    end: Option<&str>,
+    horizon: Option<u32>,
+    max_horizon: chrono::Duration,
+) -> Result<ForecastWindow, BackendError> {
+    let tz = now.timezone();
+    let start = parse_time(start, &tz)?;
+    let end = parse_time(end, &tz)?;
+    ForecastWindow::from_request(now, start, end, horizon, max_horizon)
+}
+
+/// Takes thresholds and level from the requested preset, explicitly given values take precedence.
+fn base_options(query: &WeatherQuery) -> Result<AnalysisOptions, BackendError> {
+    let mut options = match query.preset.as_deref() {
+        Some(id) => plant_preset(id)
+            .map(AnalysisOptions::from_preset)
+            .ok_or_else(|| BackendError::InvalidRequest(format!("Unknown preset '{}'", id)))?,
+        None => match (query.warning_threshold, query.danger_threshold) {
+            (Some(warning_threshold), Some(danger_threshold)) => {
+                AnalysisOptions::new(warning_threshold, danger_threshold)
+            }
+            _ => {
+                return Err(BackendError::InvalidRequest(
+                    "Either a preset or warning and danger thresholds are required".to_owned(),
+                ))
+            }
+        },
+    };
+
+    if let Some(warning_threshold) = query.warning_threshold {
+        options.warning_threshold = warning_threshold;
+    }
+    if let Some(danger_threshold) = query.danger_threshold {
+        options.danger_threshold = danger_threshold;
+    }
+    if query.level.is_some() {
+        options.level = parse_level(query.level.as_deref())?;
+    }
+    options.heat_thresholds = match (query.heat_warning_threshold, query.heat_danger_threshold) {
+        (Some(warning_threshold), Some(danger_threshold)) => {
+            Some((warning_threshold, danger_threshold))
+        }
+        (None, None) => None,
+        _ => {
+            return Err(BackendError::InvalidRequest(
+                "Heat warning and danger thresholds must be given together".to_owned(),
+            ))
+        }
+    };
+
+    Ok(options)
+}
+
+fn parse_level(level: Option<&str>) -> Result<TemperatureLevel, BackendError> {
+    level
+        .map_or(Ok(TemperatureLevel::default()), str::parse)
+        .map_err(BackendError::InvalidRequest)
+}
+
+fn parse_time(time: Option<&str>, tz: &Tz) -> Result<Option<DateTime<Tz>>, BackendError> {
+    time.map(|time| {
+        DateTime::parse_from_rfc3339(time)
+            .map(|time| time.with_timezone(tz))
+            .map_err(|e| BackendError::InvalidRequest(format!("Invalid time '{}': {}", time, e)))
+    })
+    .transpose()
+}
+
+#[derive(Clone)]
+struct Providers {
+    providers: Arc<Vec<Box<dyn WeatherProvider>>>,
+    client: reqwest::blocking::Client,
+}
+
+impl Providers {
+    /// Tries all configured providers in order and returns the first forecast that could be
+    /// retrieved, or the error of the last provider if none of them succeeded.
+    fn fetch_forecast(
+        &self,
+        lat: f32,
+        lon: f32,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Forecast, BackendError> {
+        let mut last_error =
+            BackendError::NetworkError("No weather provider configured".to_owned());
+
+        for provider in self.providers.iter() {
+            match self.fetch_from(provider.as_ref(), lat, lon, start, end) {
+                Ok(mut forecast) => {
+                    forecast.provider = Some(provider.name().to_owned());
+                    return Ok(forecast);
+                }
+                Err(e) => {
+                    warn!("Could not get forecast from {}: {}", provider.name(), e);
+                    last_error = e;
+                }
+            }
         }
-        Err(e) => {
-            error!("Error parsing brightsky data: {}", e);
-            let api_error: BrightskyApiError = serde_json::from_str(brightsky_response)?;
-            Ok(Err(api_error.into()))
+
+        Err(last_error)
+    }
+
+    fn fetch_from(
+        &self,
+        provider: &dyn WeatherProvider,
+        lat: f32,
+        lon: f32,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Forecast, BackendError> {
+        let url = provider.forecast_url(lat, lon, start, end);
+
+        debug!("Pulling weather data from {} at {}", provider.name(), url);
+
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .map_err(|e| BackendError::NetworkError(e.to_string()))?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(BackendError::NetworkError(format!(
+                "{} responded with {}",
+                provider.name(),
+                status
+            )));
         }
+
+        let body = response
+            .text()
+            .map_err(|e| BackendError::NetworkError(e.to_string()))?;
+
+        debug!("Received data:\n{}", body);
+
+        provider.parse_forecast(&body)
     }
 }
 
 struct RootDir(String);
-struct BrightSkyEndpoint(String);
+struct MaxHorizon(chrono::Duration);
+struct MaxBatchSize(usize);
+
+/// Push alerts are only available if a VAPID key is configured.
+struct Push {
+    sender: Option<PushSender>,
+}
+
+impl Push {
+    fn sender(&self) -> Result<&PushSender, BackendError> {
+        self.sender.as_ref().ok_or_else(|| {
+            BackendError::InvalidRequest("Push alerts are not enabled on this server".to_owned())
+        })
+    }
+}
+
+/// Webhooks make the server send requests to arbitrary URLs, so they have to be enabled explicitly.
+struct Webhooks {
+    sender: Option<WebhookSender>,
+}
+
+impl Webhooks {
+    fn sender(&self) -> Result<&WebhookSender, BackendError> {
+        self.sender.as_ref().ok_or_else(|| {
+            BackendError::InvalidRequest("Webhooks are not enabled on this server".to_owned())
+        })
+    }
+}
+
+/// Email alerts are only available if an SMTP server is configured.
+struct Email {
+    sender: Option<EmailSender>,
+    /// base URL of the server for links in emails
+    public_url: String,
+}
+
+impl Email {
+    fn sender(&self) -> Result<&EmailSender, BackendError> {
+        self.sender.as_ref().ok_or_else(|| {
+            BackendError::InvalidRequest("Email alerts are not enabled on this server".to_owned())
+        })
+    }
+}
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     dotenv().ok();
     env_logger::init();
 
     rocket::ignite()
-        .mount("/", routes![index, weather, files])
+        .mount(
+            "/",
+            routes![
+                index,
+                weather,
+                weather_batch,
+                forecast,
+                max_horizon,
+                push_key,
+                push_unsubscribe,
+                create_subscription,
+                get_subscription,
+                update_subscription,
+                delete_subscription,
+                confirm_subscription,
+                unsubscribe,
+                files
+            ],
+        )
         .attach(AdHoc::on_attach("Root Dir", |rocket| {
             let root_dir = rocket
                 .config()
@@ -113,14 +702,296 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
             Ok(rocket.manage(RootDir(root_dir)))
         }))
-        .attach(AdHoc::on_attach("BrightSky Api Endpoint", |rocket| {
-            let brightsky_endpoint = rocket
+        .attach(AdHoc::on_attach("Weather Providers", |rocket| {
+            let config = rocket.config();
+
+            let names: Vec<String> = if let Ok(names) = config.get_slice("frost_weather_providers")
+            {
+                names
+                    .iter()
+                    .filter_map(|name| name.as_str())
+                    .map(str::to_owned)
+                    .collect()
+            } else {
+                let name = config
+                    .get_str("frost_weather_provider")
+                    .unwrap_or("brightsky");
+                vec![name.to_owned()]
+            };
+
+            let mut providers: Vec<Box<dyn WeatherProvider>> = Vec::new();
+
+            for name in names {
+                match name.as_str() {
+                    "brightsky" => {
+                        let endpoint = config
+                            .get_str("frost_brightsky_endpoint")
+                            .unwrap_or(brightsky::DEFAULT_ENDPOINT)
+                            .to_string();
+                        providers.push(Box::new(BrightSky::new(endpoint)));
+                    }
+                    "open-meteo" => {
+                        let endpoint = config
+                            .get_str("frost_open_meteo_endpoint")
+                            .unwrap_or(open_meteo::DEFAULT_ENDPOINT)
+                            .to_string();
+                        providers.push(Box::new(OpenMeteo::new(endpoint)));
+                    }
+                    other => {
+                        error!("Unknown weather provider: {}", other);
+                        return Err(rocket);
+                    }
+                }
+            }
+
+            let timeout = config
+                .get_int("frost_provider_timeout")
+                .unwrap_or(10)
+                .max(1) as u64;
+            let client = match reqwest::blocking::Client::builder()
+                .timeout(Duration::from_secs(timeout))
+                .build()
+            {
+                Ok(client) => client,
+                Err(e) => {
+                    error!("Could not create http client: {}", e);
+                    return Err(rocket);
+                }
+            };
+
+            info!(
+                "Using weather providers {:?}",
+                providers.iter().map(|p| p.name()).collect::<Vec<&str>>()
+            );
+
+            Ok(rocket.manage(Providers {
+                providers: Arc::new(providers),
+                client,
+            }))
+        }))
+        .attach(AdHoc::on_attach("Forecast Cache", |rocket| {
+            let config = rocket.config();
+            let ttl = config.get_int("frost_cache_ttl").unwrap_or(600).max(0) as u64;
+            let size = config.get_int("frost_cache_size").unwrap_or(1000).max(0) as usize;
+
+            let cache = ForecastCache::new(Duration::from_secs(ttl), size);
+
+            Ok(rocket.manage(Arc::new(cache)))
+        }))
+        .attach(AdHoc::on_attach("Max Forecast Horizon", |rocket| {
+            let max_horizon = rocket
                 .config()
-                .get_str("frost_brightsky_endpoint")
-                .unwrap_or("https://api.brightsky.dev/weather")
-                .to_string();
+                .get_int("frost_max_horizon")
+                .unwrap_or(240)
+                .max(1);
+
+            Ok(rocket.manage(MaxHorizon(chrono::Duration::hours(max_horizon))))
+        }))
+        .attach(AdHoc::on_attach("Max Batch Size", |rocket| {
+            let max_batch_size = rocket
+                .config()
+                .get_int("frost_max_batch_size")
+                .unwrap_or(20)
+                .max(1) as usize;
+
+            Ok(rocket.manage(MaxBatchSize(max_batch_size)))
+        }))
+        .attach(AdHoc::on_attach("Push Alerts", |rocket| {
+            let config = rocket.config();
+
+            let sender = if let Ok(path) = config.get_str("frost_vapid_private_key") {
+                let subject = config
+                    .get_str("frost_vapid_subject")
+                    .unwrap_or("mailto:admin@localhost")
+                    .to_owned();
+                let private_key = match std::fs::read(path) {
+                    Ok(private_key) => private_key,
+                    Err(e) => {
+                        error!("Could not read VAPID private key {}: {}", path, e);
+                        return Err(rocket);
+                    }
+                };
+                let timeout = config.get_int("frost_push_timeout").unwrap_or(10).max(1) as u64;
+                let client = match net::client_builder(Duration::from_secs(timeout)).build() {
+                    Ok(client) => client,
+                    Err(e) => {
+                        error!("Could not create http client: {}", e);
+                        return Err(rocket);
+                    }
+                };
+                match PushSender::new(private_key, subject, client) {
+                    Ok(sender) => Some(sender),
+                    Err(e) => {
+                        error!("Could not load VAPID private key {}: {}", path, e);
+                        return Err(rocket);
+                    }
+                }
+            } else {
+                info!("No VAPID private key configured, push alerts are disabled");
+                None
+            };
+
+            Ok(rocket.manage(Push { sender }))
+        }))
+        .attach(AdHoc::on_attach("Email Alerts", |rocket| {
+            let config = rocket.config();
 
-            Ok(rocket.manage(BrightSkyEndpoint(brightsky_endpoint)))
+            let sender = if let Ok(host) = config.get_str("frost_smtp_host") {
+                let tls = config.get_str("frost_smtp_tls").unwrap_or("starttls");
+                let tls = match tls.parse::<SmtpTls>() {
+                    Ok(tls) => tls,
+                    Err(e) => {
+                        error!("{}", e);
+                        return Err(rocket);
+                    }
+                };
+                let default_port = match tls {
+                    SmtpTls::None => 25,
+                    SmtpTls::StartTls => 587,
+                    SmtpTls::Tls => 465,
+                };
+                let smtp = SmtpConfig {
+                    host: host.to_owned(),
+                    port: config
+                        .get_int("frost_smtp_port")
+                        .map(|port| port as u16)
+                        .unwrap_or(default_port),
+                    tls,
+                    username: config
+                        .get_str("frost_smtp_username")
+                        .ok()
+                        .map(str::to_owned),
+                    password: config
+                        .get_str("frost_smtp_password")
+                        .ok()
+                        .map(str::to_owned),
+                    from: config
+                        .get_str("frost_smtp_from")
+                        .unwrap_or("Frost <frost@localhost>")
+                        .to_owned(),
+                    timeout: Duration::from_secs(
+                        config.get_int("frost_smtp_timeout").unwrap_or(30).max(1) as u64,
+                    ),
+                };
+                match EmailSender::new(&smtp) {
+                    Ok(sender) => Some(sender),
+                    Err(e) => {
+                        error!("Could not set up SMTP server {}: {}", host, e);
+                        return Err(rocket);
+                    }
+                }
+            } else {
+                info!("No SMTP server configured, email alerts are disabled");
+                None
+            };
+
+            let public_url = config
+                .get_str("frost_public_url")
+                .unwrap_or("http://localhost:8000")
+                .to_owned();
+
+            Ok(rocket.manage(Email { sender, public_url }))
+        }))
+        .attach(AdHoc::on_attach("Webhooks", |rocket| {
+            let config = rocket.config();
+
+            let sender = if config.get_bool("frost_webhooks").unwrap_or(false) {
+                let attempts = config.get_int("frost_webhook_attempts").unwrap_or(4).max(1) as u32;
+                let backoff = config.get_int("frost_webhook_backoff").unwrap_or(2).max(0) as u64;
+                let retry_budget = config
+                    .get_int("frost_webhook_retry_budget")
+                    .unwrap_or(60)
+                    .max(0) as u64;
+                let allow_private = config
+                    .get_bool("frost_webhook_allow_private")
+                    .unwrap_or(false);
+                let timeout = config.get_int("frost_webhook_timeout").unwrap_or(10).max(1) as u64;
+                let webhook = WebhookConfig {
+                    attempts,
+                    backoff: Duration::from_secs(backoff),
+                    retry_budget: Duration::from_secs(retry_budget),
+                    allow_private,
+                    timeout: Duration::from_secs(timeout),
+                };
+                match WebhookSender::new(webhook) {
+                    Ok(sender) => Some(sender),
+                    Err(e) => {
+                        error!("Could not create http client: {}", e);
+                        return Err(rocket);
+                    }
+                }
+            } else {
+                info!("Webhooks are disabled");
+                None
+            };
+
+            Ok(rocket.manage(Webhooks { sender }))
+        }))
+        .attach(AdHoc::on_attach("Subscription Store", |rocket| {
+            let path = rocket
+                .config()
+                .get_str("frost_database")
+                .unwrap_or("./frost.db")
+                .to_owned();
+
+            match SubscriptionStore::open(&path) {
+                Ok(store) => Ok(rocket.manage(Arc::new(store))),
+                Err(e) => {
+                    error!("Could not open subscription store {}: {}", path, e);
+                    Err(rocket)
+                }
+            }
+        }))
+        .attach(AdHoc::on_launch("Alert Job", |rocket| {
+            let push = rocket
+                .state::<Push>()
+                .expect("push alerts are set up on attach")
+                .sender
+                .clone();
+            let email_state = rocket
+                .state::<Email>()
+                .expect("email alerts are set up on attach");
+            let email = email_state.sender.clone();
+            let public_url = email_state.public_url.clone();
+            let webhook = rocket
+                .state::<Webhooks>()
+                .expect("webhooks are set up on attach")
+                .sender
+                .clone();
+            if push.is_none() && email.is_none() && webhook.is_none() {
+                return;
+            }
+            let interval = rocket
+                .config()
+                .get_int("frost_alert_interval")
+                .unwrap_or(21600)
+                .max(60) as u64;
+            let job = AlertJob {
+                providers: rocket
+                    .state::<Providers>()
+                    .expect("providers are set up on attach")
+                    .clone(),
+                cache: rocket
+                    .state::<Arc<ForecastCache>>()
+                    .expect("cache is set up on attach")
+                    .clone(),
+                max_horizon: rocket
+                    .state::<MaxHorizon>()
+                    .expect("max horizon is set up on attach")
+                    .0,
+                store: rocket
+                    .state::<Arc<SubscriptionStore>>()
+                    .expect("subscription store is set up on attach")
+                    .clone(),
+                push,
+                email,
+                webhook,
+                interval: Duration::from_secs(interval),
+                public_url,
+            };
+            if let Err(e) = job.spawn() {
+                error!("Could not start alert job: {}", e);
+            }
         }))
         .launch();
 
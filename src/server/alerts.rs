@@ -0,0 +1,267 @@
+use super::email::{self, EmailError, EmailSender};
+use super::push::{PushError, PushSender};
+use super::subscriptions::{StoredSubscription, SubscriptionStore};
+use super::webhook::{WebhookError, WebhookSender};
+use crate::{analyze, Providers, WeatherQuery};
+use chrono::prelude::*;
+use frost::backend::cache::ForecastCache;
+use frost::backend::time_zone::time_zone_at;
+use frost::common::*;
+use std::fmt;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeliveryError {
+    /// the recipient is gone and the subscription should be removed
+    Expired,
+    /// the channel of the subscription is not configured on this server
+    Unavailable,
+    Failed(String),
+}
+
+impl fmt::Display for DeliveryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeliveryError::Expired => write!(f, "Recipient is gone"),
+            DeliveryError::Unavailable => write!(f, "Alert channel is not enabled"),
+            DeliveryError::Failed(e) => e.fmt(f),
+        }
+    }
+}
+
+impl From<PushError> for DeliveryError {
+    fn from(e: PushError) -> Self {
+        match e {
+            PushError::Expired => DeliveryError::Expired,
+            PushError::Failed(e) => DeliveryError::Failed(e),
+        }
+    }
+}
+
+impl From<WebhookError> for DeliveryError {
+    fn from(e: WebhookError) -> Self {
+        match e {
+            WebhookError::Expired => DeliveryError::Expired,
+            WebhookError::Failed(e) => DeliveryError::Failed(e),
+        }
+    }
+}
+
+impl From<EmailError> for DeliveryError {
+    fn from(e: EmailError) -> Self {
+        DeliveryError::Failed(e.to_string())
+    }
+}
+
+/// Periodically analyzes the forecast for every subscription and sends alerts about cold phases.
+pub struct AlertJob {
+    pub providers: Providers,
+    pub cache: Arc<ForecastCache>,
+    pub max_horizon: chrono::Duration,
+    pub store: Arc<SubscriptionStore>,
+    pub push: Option<PushSender>,
+    pub email: Option<EmailSender>,
+    pub webhook: Option<WebhookSender>,
+    pub interval: Duration,
+    /// base URL of the server for links in emails
+    pub public_url: String,
+}
+
+impl AlertJob {
+    pub fn spawn(self) -> std::io::Result<thread::JoinHandle<()>> {
+        thread::Builder::new()
+            .name("alerts".to_owned())
+            .spawn(move || loop {
+                self.run();
+                thread::sleep(self.interval);
+            })
+    }
+
+    pub fn run(&self) {
+        let subscriptions = match self.store.all() {
+            Ok(subscriptions) => subscriptions,
+            Err(e) => {
+                error!("Could not load subscriptions: {}", e);
+                return;
+            }
+        };
+
+        // shared by all webhooks, so unreachable receivers can't delay the alerts for too long
+        let mut retry_budget = self
+            .webhook
+            .as_ref()
+            .map_or(Duration::from_secs(0), WebhookSender::retry_budget);
+
+        for stored in subscriptions {
+            let id = stored.id();
+            let subscription = &stored.subscription;
+            let location = &subscription.location;
+
+            if let Some(quiet_hours) = subscription.quiet_hours {
+                let tz = time_zone_at(location.lat, location.lon);
+                if quiet_hours.contains(Utc::now().with_timezone(&tz).hour()) {
+                    debug!("Not alerting subscription {} during quiet hours", id);
+                    continue;
+                }
+            }
+
+            let query = WeatherQuery::from(location.clone());
+            let response = match analyze(&query, &self.providers, &self.cache, self.max_horizon) {
+                Ok(response) => response,
+                Err(e) => {
+                    warn!("Could not analyze weather for subscription {}: {}", id, e);
+                    continue;
+                }
+            };
+
+            let previous = subscription.last_alert.as_ref();
+            let (announcements, phases) = pending_announcements(previous, &response, Utc::now());
+            if announcements.is_empty() && previous.map(|state| &state.phases) == Some(&phases) {
+                continue;
+            }
+
+            let sent = self.deliver(&stored, &announcements, &response, &mut retry_budget);
+
+            match sent {
+                Ok(()) => {
+                    debug!("Sent {} alerts to subscription {}", announcements.len(), id);
+                    let state = AlertState {
+                        sent_at: Utc::now(),
+                        phases,
+                    };
+                    if let Err(e) = self.store.set_last_alert(id, &state) {
+                        warn!("Could not store alert state of subscription {}: {}", id, e);
+                    }
+                }
+                Err(DeliveryError::Expired) => {
+                    info!("Removing expired subscription {}", id);
+                    if let Err(e) = self.store.delete(id) {
+                        warn!("Could not remove subscription {}: {}", id, e);
+                    }
+                }
+                Err(e) => warn!("Could not send alert to subscription {}: {}", id, e),
+            }
+        }
+    }
+
+    fn deliver(
+        &self,
+        stored: &StoredSubscription,
+        announcements: &[Announcement],
+        response: &BackendResponse,
+        retry_budget: &mut Duration,
+    ) -> Result<(), DeliveryError> {
+        if announcements.is_empty() {
+            return Ok(());
+        }
+        let subscription = &stored.subscription;
+        let place_name = &subscription.place_name;
+        let alerts: Vec<PushAlert> = announcements
+            .iter()
+            .map(|announcement| announcement.alert(place_name))
+            .collect();
+
+        match &subscription.channel {
+            AlertChannel::Push(push_subscription) => {
+                let sender = self.push.as_ref().ok_or(DeliveryError::Unavailable)?;
+                for alert in &alerts {
+                    sender.send(push_subscription, alert)?;
+                }
+            }
+            AlertChannel::Email { address } => {
+                let sender = self.email.as_ref().ok_or(DeliveryError::Unavailable)?;
+                let unsubscribe_url = email::unsubscribe_url(&self.public_url, &stored.token);
+                let digest =
+                    email::digest(place_name, &alerts, &response.cold_phases, &unsubscribe_url);
+                sender.send(address, &digest)?;
+            }
+            AlertChannel::Webhook { url, secret } => {
+                let sender = self.webhook.as_ref().ok_or(DeliveryError::Unavailable)?;
+                for (announcement, alert) in announcements.iter().zip(alerts) {
+                    let payload = WebhookPayload {
+                        place_name: place_name.clone(),
+                        trigger: announcement.clone(),
+                        alert,
+                        response: response.clone(),
+                    };
+                    sender.send(url, secret, &payload, retry_budget)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Changes since the last alert, and the cold phases announced afterwards.
+pub fn pending_announcements(
+    last_alert: Option<&AlertState>,
+    response: &BackendResponse,
+    now: DateTime<Utc>,
+) -> (Vec<Announcement>, Vec<AnnouncedPhase>) {
+    let announced = last_alert.map(|state| &state.phases[..]).unwrap_or(&[]);
+    announcements(announced, &response.cold_phases, now)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use frost::backend::brightsky::BrightSky;
+    use frost::backend::provider::WeatherProvider;
+    use frost::backend::{accumulate_cold_phases, AnalysisOptions};
+
+    fn response(warning_threshold: f32, danger_threshold: f32) -> BackendResponse {
+        let data = std::fs::read_to_string("test/test.json").unwrap();
+        let data = BrightSky::default().parse_forecast(&data).unwrap();
+        let options = AnalysisOptions::new(warning_threshold, danger_threshold);
+        accumulate_cold_phases(&options, chrono_tz::Europe::Berlin, &data)
+    }
+
+    fn now() -> DateTime<Utc> {
+        Utc.ymd(2020, 4, 20).and_hms(18, 0, 0)
+    }
+
+    #[test]
+    fn test_alert() {
+        let (announcements, phases) = pending_announcements(None, &response(10.0, 7.0), now());
+
+        assert_eq!(announcements.len(), 1);
+        let alert = announcements[0].alert("Garden");
+        assert_eq!(alert.title, "DANGER in Garden");
+        assert_eq!(
+            alert.body,
+            "Temperatures as low as 6.7 °C predicted, starting Tue 04:00."
+        );
+        assert_eq!(phases.len(), 1);
+    }
+
+    #[test]
+    fn test_alert_is_not_repeated() {
+        let (_, phases) = pending_announcements(None, &response(10.0, 7.0), now());
+        let state = AlertState {
+            sent_at: now(),
+            phases,
+        };
+
+        let (announcements, _) = pending_announcements(Some(&state), &response(10.0, 7.0), now());
+        assert_eq!(announcements, vec![]);
+
+        let (announcements, phases) =
+            pending_announcements(Some(&state), &response(5.0, 0.0), now());
+        assert_eq!(announcements.len(), 1);
+        assert_eq!(
+            announcements[0].alert("Garden").title,
+            "ALL CLEAR in Garden"
+        );
+        assert_eq!(phases, vec![]);
+    }
+
+    #[test]
+    fn test_no_alert_when_warm() {
+        let (announcements, phases) = pending_announcements(None, &response(5.0, 0.0), now());
+        assert_eq!(announcements, vec![]);
+        assert_eq!(phases, vec![]);
+    }
+}
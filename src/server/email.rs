@@ -0,0 +1,446 @@
+use frost::common::*;
+use lettre::message::{Mailbox, MultiPart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+use std::fmt;
+use std::time::Duration;
+
+const TEXT_TEMPLATE: &str = include_str!("../../templates/alert.txt");
+const HTML_TEMPLATE: &str = include_str!("../../templates/alert.html");
+const CONFIRMATION_TEXT_TEMPLATE: &str = include_str!("../../templates/confirmation.txt");
+const CONFIRMATION_HTML_TEMPLATE: &str = include_str!("../../templates/confirmation.html");
+
+const PHASE_TIME_FORMAT: &str = "%a %d.%m. %H:%M";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmailError(String);
+
+impl fmt::Display for EmailError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl std::error::Error for EmailError {}
+
+impl From<lettre::transport::smtp::Error> for EmailError {
+    fn from(e: lettre::transport::smtp::Error) -> Self {
+        EmailError(e.to_string())
+    }
+}
+
+impl From<lettre::error::Error> for EmailError {
+    fn from(e: lettre::error::Error) -> Self {
+        EmailError(e.to_string())
+    }
+}
+
+impl From<lettre::address::AddressError> for EmailError {
+    fn from(e: lettre::address::AddressError) -> Self {
+        EmailError(e.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SmtpTls {
+    /// plain connection, only suitable for a local SMTP server or sink
+    None,
+    StartTls,
+    /// implicit TLS, usually on port 465
+    Tls,
+}
+
+impl std::str::FromStr for SmtpTls {
+    type Err = EmailError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(SmtpTls::None),
+            "starttls" => Ok(SmtpTls::StartTls),
+            "tls" => Ok(SmtpTls::Tls),
+            other => Err(EmailError(format!("Unknown SMTP TLS mode: {}", other))),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    pub tls: SmtpTls,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// sender of the alerts, e.g. `Frost <frost@example.com>`
+    pub from: String,
+    pub timeout: Duration,
+}
+
+/// A rendered email.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Digest {
+    pub subject: String,
+    pub text: String,
+    pub html: String,
+}
+
+/// Sends alerts as email digests of the upcoming cold phases.
+#[derive(Clone)]
+pub struct EmailSender {
+    transport: SmtpTransport,
+    from: Mailbox,
+}
+
+impl EmailSender {
+    pub fn new(config: &SmtpConfig) -> Result<EmailSender, EmailError> {
+        let builder = match config.tls {
+            SmtpTls::None => SmtpTransport::builder_dangerous(&config.host),
+            SmtpTls::StartTls => SmtpTransport::starttls_relay(&config.host)?,
+            SmtpTls::Tls => SmtpTransport::relay(&config.host)?,
+        };
+        let mut builder = builder.port(config.port).timeout(Some(config.timeout));
+        if let (Some(username), Some(password)) = (&config.username, &config.password) {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+
+        Ok(EmailSender {
+            transport: builder.build(),
+            from: config.from.parse()?,
+        })
+    }
+
+    pub fn send(&self, address: &str, digest: &Digest) -> Result<(), EmailError> {
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(address.parse()?)
+            .subject(&digest.subject)
+            .multipart(MultiPart::alternative_plain_html(
+                digest.text.clone(),
+                digest.html.clone(),
+            ))?;
+
+        self.transport.send(&message)?;
+        Ok(())
+    }
+}
+
+impl fmt::Debug for EmailSender {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EmailSender")
+            .field("from", &self.from)
+            .finish()
+    }
+}
+
+/// Link confirming the subscription with this token.
+pub fn confirm_url(public_url: &str, token: &str) -> String {
+    format!(
+        "{}/subscriptions/confirm/{}",
+        public_url.trim_end_matches('/'),
+        token
+    )
+}
+
+/// Link removing the subscription with this token.
+pub fn unsubscribe_url(public_url: &str, token: &str) -> String {
+    format!(
+        "{}/subscriptions/unsubscribe/{}",
+        public_url.trim_end_matches('/'),
+        token
+    )
+}
+
+/// Checks that alerts can be sent to the address.
+pub fn validate_address(address: &str) -> Result<(), EmailError> {
+    address.parse::<Mailbox>()?;
+    Ok(())
+}
+
+/// Renders the alerts followed by all upcoming cold phases of the place.
+pub fn digest(
+    place_name: &str,
+    alerts: &[PushAlert],
+    phases: &[ColdPhase],
+    unsubscribe_url: &str,
+) -> Digest {
+    let subject = match alerts {
+        [alert] => alert.title.clone(),
+        _ => format!("{} frost alerts for {}", alerts.len(), place_name),
+    };
+
+    let text_changes = alerts
+        .iter()
+        .map(|alert| format!("{}: {}", alert.title, alert.body))
+        .collect::<Vec<String>>()
+        .join("\n");
+    let html_changes = alerts
+        .iter()
+        .map(|alert| {
+            format!(
+                "      <li><strong>{}</strong>: {}</li>",
+                escape_html(&alert.title),
+                escape_html(&alert.body)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let (text_phases, html_phases) = if phases.is_empty() {
+        ("None".to_owned(), "      <tr><td>None</td></tr>".to_owned())
+    } else {
+        let text = phases
+            .iter()
+            .map(|phase| {
+                format!(
+                    "- {} to {}: {}, as low as {} °C{}",
+                    phase.start.format(PHASE_TIME_FORMAT),
+                    phase.end.format(PHASE_TIME_FORMAT),
+                    phase.record_type,
+                    phase.min_temp,
+                    preset_suffix(phase)
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n");
+        let html = phases
+            .iter()
+            .map(|phase| {
+                format!(
+                    "      <tr><td>{} to {}</td><td><strong>{}</strong></td><td>as low as {} °C{}</td></tr>",
+                    phase.start.format(PHASE_TIME_FORMAT),
+                    phase.end.format(PHASE_TIME_FORMAT),
+                    phase.record_type,
+                    phase.min_temp,
+                    escape_html(&preset_suffix(phase))
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n");
+        (text, html)
+    };
+
+    Digest {
+        subject,
+        text: render(
+            TEXT_TEMPLATE,
+            &[
+                ("place_name", place_name),
+                ("changes", &text_changes),
+                ("phases", &text_phases),
+                ("unsubscribe_url", unsubscribe_url),
+            ],
+        ),
+        html: render(
+            HTML_TEMPLATE,
+            &[
+                ("place_name", &escape_html(place_name)),
+                ("changes", &html_changes),
+                ("phases", &html_phases),
+                ("unsubscribe_url", &escape_html(unsubscribe_url)),
+            ],
+        ),
+    }
+}
+
+/// Renders the mail asking the recipient to confirm a new subscription, no alerts are sent
+/// before the confirmation link was opened.
+pub fn confirmation(place_name: &str, confirm_url: &str, unsubscribe_url: &str) -> Digest {
+    Digest {
+        subject: format!("Confirm your frost alerts for {}", place_name),
+        text: render(
+            CONFIRMATION_TEXT_TEMPLATE,
+            &[
+                ("place_name", place_name),
+                ("confirm_url", confirm_url),
+                ("unsubscribe_url", unsubscribe_url),
+            ],
+        ),
+        html: render(
+            CONFIRMATION_HTML_TEMPLATE,
+            &[
+                ("place_name", &escape_html(place_name)),
+                ("confirm_url", &escape_html(confirm_url)),
+                ("unsubscribe_url", &escape_html(unsubscribe_url)),
+            ],
+        ),
+    }
+}
+
+fn preset_suffix(phase: &ColdPhase) -> String {
+    match &phase.preset {
+        Some(preset) => format!(" for {}", preset),
+        None => String::new(),
+    }
+}
+
+/// Replaces the `{{name}}` placeholders of the template.
+fn render(template: &str, values: &[(&str, &str)]) -> String {
+    values
+        .iter()
+        .fold(template.to_owned(), |text, (name, value)| {
+            text.replace(&format!("{{{{{}}}}}", name), value)
+        })
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use frost::backend::brightsky::BrightSky;
+    use frost::backend::provider::WeatherProvider;
+    use frost::backend::{accumulate_cold_phases, AnalysisOptions};
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    fn phases() -> Vec<ColdPhase> {
+        let data = std::fs::read_to_string("test/test.json").unwrap();
+        let data = BrightSky::default().parse_forecast(&data).unwrap();
+        let options = AnalysisOptions::new(10.0, 7.0);
+        accumulate_cold_phases(&options, chrono_tz::Europe::Berlin, &data).cold_phases
+    }
+
+    fn alert() -> PushAlert {
+        PushAlert {
+            title: "DANGER in Garden".to_owned(),
+            body: "Temperatures as low as 6.7 °C predicted, starting Tue 04:00.".to_owned(),
+            tag: "frost-Garden".to_owned(),
+        }
+    }
+
+    /// Accepts a single mail and returns its headers and content as received after `DATA`.
+    fn smtp_sink() -> (u16, thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            stream.write_all(b"220 localhost ESMTP sink\r\n").unwrap();
+            let mut data = String::new();
+            let mut in_data = false;
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap() == 0 {
+                    break;
+                }
+                if in_data {
+                    if line == ".\r\n" {
+                        stream.write_all(b"250 OK\r\n").unwrap();
+                        break;
+                    }
+                    data.push_str(&line);
+                } else if line.starts_with("DATA") {
+                    in_data = true;
+                    stream
+                        .write_all(b"354 End data with <CR><LF>.<CR><LF>\r\n")
+                        .unwrap();
+                } else {
+                    stream.write_all(b"250 OK\r\n").unwrap();
+                }
+            }
+            data
+        });
+        (port, handle)
+    }
+
+    const UNSUBSCRIBE_URL: &str = "https://frost.example/subscriptions/unsubscribe/abc&def";
+
+    #[test]
+    fn test_digest() {
+        let digest = digest("Garden <3", &[alert()], &phases(), UNSUBSCRIBE_URL);
+
+        assert_eq!(digest.subject, "DANGER in Garden");
+        assert!(digest.text.starts_with("Frost alert for Garden <3\n"));
+        assert!(digest.text.contains(
+            "DANGER in Garden: Temperatures as low as 6.7 °C predicted, starting Tue 04:00."
+        ));
+        assert!(digest
+            .text
+            .contains("- Tue 21.04. 04:00 to Tue 21.04. 09:00: danger, as low as 6.7 °C"));
+        assert!(digest
+            .html
+            .contains("<h1 style=\"color: #5400ff;\">Frost alert for Garden &lt;3</h1>"));
+        assert!(digest.html.contains(
+            "<tr><td>Tue 21.04. 04:00 to Tue 21.04. 09:00</td><td><strong>danger</strong></td><td>as low as 6.7 °C</td></tr>"
+        ));
+        assert!(digest
+            .text
+            .contains(&format!("unsubscribe: {}", UNSUBSCRIBE_URL)));
+        assert!(digest.html.contains(
+            "<a href=\"https://frost.example/subscriptions/unsubscribe/abc&amp;def\">Unsubscribe</a>"
+        ));
+    }
+
+    #[test]
+    fn test_confirmation() {
+        let confirmation = confirmation(
+            "Garden",
+            "https://frost.example/subscriptions/confirm/abc",
+            UNSUBSCRIBE_URL,
+        );
+
+        assert_eq!(confirmation.subject, "Confirm your frost alerts for Garden");
+        assert!(confirmation
+            .text
+            .contains("https://frost.example/subscriptions/confirm/abc"));
+        assert!(confirmation.text.contains(UNSUBSCRIBE_URL));
+        assert!(confirmation
+            .html
+            .contains("<a href=\"https://frost.example/subscriptions/confirm/abc\">"));
+        assert!(!confirmation.html.contains("{{"));
+    }
+
+    #[test]
+    fn test_all_clear_digest() {
+        let all_clear = PushAlert {
+            title: "ALL CLEAR in Garden".to_owned(),
+            body: "The danger for Tue 04:00 is no longer predicted.".to_owned(),
+            tag: "frost-Garden".to_owned(),
+        };
+        let digest = digest("Garden", &[all_clear, alert()], &[], UNSUBSCRIBE_URL);
+
+        assert_eq!(digest.subject, "2 frost alerts for Garden");
+        assert!(digest.text.contains("Upcoming cold phases:\nNone\n"));
+    }
+
+    #[test]
+    fn test_send() {
+        let (port, sink) = smtp_sink();
+        let sender = EmailSender::new(&SmtpConfig {
+            host: "127.0.0.1".to_owned(),
+            port,
+            tls: SmtpTls::None,
+            username: None,
+            password: None,
+            from: "Frost <frost@example.com>".to_owned(),
+            timeout: Duration::from_secs(5),
+        })
+        .unwrap();
+
+        sender
+            .send(
+                "gardener@example.com",
+                &digest("Garden", &[alert()], &phases(), UNSUBSCRIBE_URL),
+            )
+            .unwrap();
+
+        let mail = sink.join().unwrap();
+        assert!(mail.contains("From: Frost <frost@example.com>\r\n"));
+        assert!(mail.contains("To: gardener@example.com\r\n"));
+        assert!(mail.contains("Subject: DANGER in Garden\r\n"));
+        assert!(mail.contains("Content-Type: multipart/alternative"));
+        assert!(mail.contains("Content-Type: text/plain; charset=utf-8"));
+        assert!(mail.contains("Content-Type: text/html; charset=utf-8"));
+    }
+
+    #[test]
+    fn test_invalid_address() {
+        assert!(validate_address("gardener@example.com").is_ok());
+        assert!(validate_address("not an address").is_err());
+    }
+}
@@ -0,0 +1,6 @@
+pub mod alerts;
+pub mod email;
+pub mod net;
+pub mod push;
+pub mod subscriptions;
+pub mod webhook;
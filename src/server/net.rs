@@ -0,0 +1,90 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+
+/// Client for requests to URLs given by subscribers. Redirects are not followed, they could lead
+/// to hosts that were never validated.
+pub fn client_builder(timeout: Duration) -> reqwest::blocking::ClientBuilder {
+    reqwest::blocking::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .timeout(timeout)
+}
+
+/// Resolves the host of the URL, failing unless all of its addresses are public, so subscribers
+/// can't make the server send requests into its own network.
+pub fn public_addresses(url: &reqwest::Url) -> Result<Vec<SocketAddr>, String> {
+    let addresses = url
+        .socket_addrs(|| None)
+        .map_err(|e| format!("Could not resolve host: {}", e))?;
+    if addresses.is_empty() {
+        return Err("Could not resolve host".to_owned());
+    }
+    match addresses.iter().find(|address| !is_public(address.ip())) {
+        Some(address) => Err(format!(
+            "Host resolves to non-public address {}",
+            address.ip()
+        )),
+        None => Ok(addresses),
+    }
+}
+
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => is_public_v6(ip),
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [first, second, ..] = ip.octets();
+    !(ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        // "this network" and carrier-grade NAT
+        || first == 0
+        || (first == 100 && (64..128).contains(&second)))
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    let segments = ip.segments();
+    if let [0, 0, 0, 0, 0, 0xffff, high, low] = segments {
+        return is_public_v4(Ipv4Addr::from(((high as u32) << 16) | low as u32));
+    }
+    !(ip.is_loopback()
+        || ip.is_unspecified()
+        || ip.is_multicast()
+        // unique local
+        || (segments[0] & 0xfe00) == 0xfc00
+        // link-local
+        || (segments[0] & 0xffc0) == 0xfe80)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn check(url: &str) -> Result<Vec<SocketAddr>, String> {
+        public_addresses(&reqwest::Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn test_public_addresses() {
+        assert_eq!(
+            check("https://93.184.216.34/hook").unwrap(),
+            vec!["93.184.216.34:443".parse().unwrap()]
+        );
+        assert!(check("https://127.0.0.1/hook").is_err());
+        assert!(check("https://10.0.0.1/hook").is_err());
+        assert!(check("https://192.168.1.10:8443/hook").is_err());
+        assert!(check("https://100.64.0.1/hook").is_err());
+        assert!(check("https://169.254.169.254/latest/meta-data").is_err());
+        assert!(check("https://[::1]/hook").is_err());
+        assert!(check("https://[fd00::1]/hook").is_err());
+        assert!(check("https://[fe80::1]/hook").is_err());
+        assert!(check("https://[::ffff:127.0.0.1]/hook").is_err());
+        assert!(check("https://[2606:2800:220:1::]/hook").is_ok());
+    }
+}
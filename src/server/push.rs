@@ -0,0 +1,247 @@
+use super::net::public_addresses;
+use frost::common::*;
+use std::fmt;
+use std::sync::Arc;
+use web_push::{
+    ContentEncoding, SubscriptionInfo, VapidSignature, VapidSignatureBuilder, WebPushMessageBuilder,
+};
+
+/// How long push services keep alerts for offline devices, warnings are outdated after a day.
+const ALERT_TTL: u32 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PushError {
+    /// the subscription is no longer valid and should be removed
+    Expired,
+    Failed(String),
+}
+
+impl fmt::Display for PushError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PushError::Expired => write!(f, "Push subscription expired"),
+            PushError::Failed(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for PushError {}
+
+impl From<web_push::WebPushError> for PushError {
+    fn from(e: web_push::WebPushError) -> Self {
+        PushError::Failed(e.to_string())
+    }
+}
+
+/// Sends VAPID signed, encrypted alerts to push services.
+#[derive(Debug, Clone)]
+pub struct PushSender {
+    /// VAPID private key in PEM format
+    private_key: Arc<Vec<u8>>,
+    /// contact of the server operator, e.g. `mailto:admin@example.com`
+    subject: String,
+    /// VAPID public key, base64 url encoded as expected by `pushManager.subscribe`
+    public_key: String,
+    client: reqwest::blocking::Client,
+}
+
+impl PushSender {
+    pub fn new(
+        private_key: Vec<u8>,
+        subject: String,
+        client: reqwest::blocking::Client,
+    ) -> Result<PushSender, PushError> {
+        // the public key is only exposed through a signature, the audience does not matter here
+        let info = SubscriptionInfo::new("https://push.invalid", "", "");
+        let signature = sign(&private_key, &subject, &info)?;
+        // already base64 url encoded
+        let public_key = signature.auth_k.clone();
+
+        Ok(PushSender {
+            private_key: Arc::new(private_key),
+            subject,
+            public_key,
+            client,
+        })
+    }
+
+    pub fn public_key(&self) -> &str {
+        &self.public_key
+    }
+
+    pub fn send(
+        &self,
+        subscription: &PushSubscription,
+        alert: &PushAlert,
+    ) -> Result<(), PushError> {
+        let info = SubscriptionInfo::new(
+            &subscription.endpoint,
+            &subscription.keys.p256dh,
+            &subscription.keys.auth,
+        );
+        let signature = sign(&self.private_key, &self.subject, &info)?;
+        let content = serde_json::to_vec(alert).expect("PushAlert can always be serialized");
+
+        let mut builder = WebPushMessageBuilder::new(&info)?;
+        builder.set_payload(ContentEncoding::Aes128Gcm, &content);
+        builder.set_vapid_signature(signature);
+        builder.set_ttl(ALERT_TTL);
+        let message = builder.build()?;
+
+        let payload = message
+            .payload
+            .ok_or_else(|| PushError::Failed("Push message has no payload".to_owned()))?;
+
+        let mut request = self
+            .client
+            .post(&subscription.endpoint)
+            .header("TTL", message.ttl.to_string())
+            .header("Content-Encoding", payload.content_encoding)
+            .header("Content-Type", "application/octet-stream");
+        for (name, value) in &payload.crypto_headers {
+            request = request.header(*name, value.as_str());
+        }
+
+        let response = request
+            .body(payload.content)
+            .send()
+            .map_err(|e| PushError::Failed(e.to_string()))?;
+
+        match response.status().as_u16() {
+            200..=299 => Ok(()),
+            404 | 410 => Err(PushError::Expired),
+            status => Err(PushError::Failed(format!(
+                "Push service responded with {}",
+                status
+            ))),
+        }
+    }
+}
+
+fn sign(
+    private_key: &[u8],
+    subject: &str,
+    info: &SubscriptionInfo,
+) -> Result<VapidSignature, PushError> {
+    let mut builder = VapidSignatureBuilder::from_pem(private_key, info)?;
+    builder.add_claim("sub", subject);
+    Ok(builder.build()?)
+}
+
+/// Checks that the endpoint of a subscription is a push service reachable via HTTPS, as required
+/// by the Push API, at a public address, so subscribers can't make the server post to arbitrary
+/// addresses.
+pub fn validate_endpoint(endpoint: &str) -> Result<(), PushError> {
+    let parsed = reqwest::Url::parse(endpoint).map_err(|e| PushError::Failed(e.to_string()))?;
+    if parsed.scheme() != "https" {
+        return Err(PushError::Failed(format!(
+            "Unsupported URL scheme: {}",
+            parsed.scheme()
+        )));
+    }
+    public_addresses(&parsed).map_err(PushError::Failed)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    // keys of the user agent from the example in RFC 8291
+    const P256DH: &str =
+        "BCVxsr7N_eNgVRqvHtD0zTZsEc6-VV-JvLexhqUzORcxaOzi6-AYWXvTBHm4bjyPjs7Vd8pZGH6SRpkNtoIAiw4";
+    const AUTH: &str = "BTBZMqHH6r4Tts7J_aSIgg";
+
+    fn sender() -> PushSender {
+        let private_key = std::fs::read("test/vapid_private.pem").unwrap();
+        PushSender::new(
+            private_key,
+            "mailto:admin@example.com".to_owned(),
+            reqwest::blocking::Client::new(),
+        )
+        .unwrap()
+    }
+
+    /// Accepts a single request, answers it with `status` and returns the received headers.
+    fn mock_push_service(status: &'static str) -> (String, thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}/push/test", listener.local_addr().unwrap());
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0; 4096];
+            while !String::from_utf8_lossy(&request).contains("\r\n\r\n") {
+                let read = stream.read(&mut buffer).unwrap();
+                request.extend_from_slice(&buffer[..read]);
+            }
+            let response = format!("HTTP/1.1 {}\r\nContent-Length: 0\r\n\r\n", status);
+            stream.write_all(response.as_bytes()).unwrap();
+            let request = String::from_utf8_lossy(&request).to_string();
+            request.split("\r\n\r\n").next().unwrap().to_lowercase()
+        });
+        (endpoint, handle)
+    }
+
+    fn subscription(endpoint: String) -> PushSubscription {
+        PushSubscription {
+            endpoint,
+            keys: PushKeys {
+                p256dh: P256DH.to_owned(),
+                auth: AUTH.to_owned(),
+            },
+        }
+    }
+
+    fn alert() -> PushAlert {
+        PushAlert {
+            title: "DANGER".to_owned(),
+            body: "Temperatures as low as -2 °C predicted.".to_owned(),
+            tag: "frost".to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_public_key() {
+        assert_eq!(
+            sender().public_key(),
+            "BLl5TB-YklXDv9htGvOV7NljS3cAz5Ebqx5prlU9qyRuuH-1t-x_EZWdGITwlkX4iPNFzrZbM3EZmH1AHNsyZKg"
+        );
+    }
+
+    #[test]
+    fn test_send() {
+        let (endpoint, mock) = mock_push_service("201 Created");
+
+        sender().send(&subscription(endpoint), &alert()).unwrap();
+
+        let headers = mock.join().unwrap();
+        assert!(headers.starts_with("post /push/test"));
+        assert!(headers.contains("content-encoding: aes128gcm"));
+        assert!(headers.contains(&format!("ttl: {}", ALERT_TTL)));
+        assert!(headers.contains("authorization: vapid t="));
+    }
+
+    #[test]
+    fn test_expired_subscription() {
+        let (endpoint, mock) = mock_push_service("410 Gone");
+
+        let result = sender().send(&subscription(endpoint), &alert());
+
+        mock.join().unwrap();
+        assert_eq!(result, Err(PushError::Expired));
+    }
+
+    #[test]
+    fn test_validate_endpoint() {
+        assert!(validate_endpoint("https://93.184.216.34/fcm/send/abc").is_ok());
+        assert!(validate_endpoint("http://93.184.216.34/fcm/send/abc").is_err());
+        assert!(validate_endpoint("https://127.0.0.1/push").is_err());
+        assert!(validate_endpoint("https://10.0.0.1/push").is_err());
+        assert!(validate_endpoint("https://169.254.169.254/latest/meta-data").is_err());
+        assert!(validate_endpoint("file:///etc/passwd").is_err());
+        assert!(validate_endpoint("not a url").is_err());
+    }
+}
@@ -0,0 +1,489 @@
+use frost::common::*;
+use rusqlite::{params, Connection, ErrorCode, OptionalExtension, Row};
+use std::fmt;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Schema migrations, the number of applied migrations is tracked in `user_version`.
+const MIGRATIONS: &[&str] = &[
+    "
+    CREATE TABLE subscriptions (
+        id INTEGER PRIMARY KEY,
+        channel_key TEXT NOT NULL UNIQUE,
+        channel TEXT NOT NULL,
+        place_name TEXT NOT NULL,
+        location TEXT NOT NULL,
+        quiet_hours TEXT,
+        last_alert TEXT
+    );
+",
+    // existing subscriptions were made before confirmations were required
+    "
+    ALTER TABLE subscriptions ADD COLUMN confirmed INTEGER NOT NULL DEFAULT 1;
+    ALTER TABLE subscriptions ADD COLUMN token TEXT;
+    UPDATE subscriptions SET token = lower(hex(randomblob(16)));
+    CREATE UNIQUE INDEX subscriptions_token ON subscriptions (token);
+",
+];
+
+const COLUMNS: &str = "id, channel, place_name, location, quiet_hours, last_alert, token";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StoreError {
+    /// another subscription already alerts the same recipient
+    Conflict,
+    Failed(String),
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::Conflict => write!(
+                f,
+                "The recipient is already subscribed, update or delete that subscription instead"
+            ),
+            StoreError::Failed(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+impl From<rusqlite::Error> for StoreError {
+    fn from(e: rusqlite::Error) -> Self {
+        match e {
+            rusqlite::Error::SqliteFailure(ref error, _)
+                if error.code == ErrorCode::ConstraintViolation =>
+            {
+                StoreError::Conflict
+            }
+            e => StoreError::Failed(e.to_string()),
+        }
+    }
+}
+
+impl From<serde_json::Error> for StoreError {
+    fn from(e: serde_json::Error) -> Self {
+        StoreError::Failed(e.to_string())
+    }
+}
+
+impl From<StoreError> for BackendError {
+    fn from(e: StoreError) -> Self {
+        match e {
+            StoreError::Conflict => BackendError::InvalidRequest(e.to_string()),
+            StoreError::Failed(e) => BackendError::StorageError(e),
+        }
+    }
+}
+
+/// A subscription as stored, with the token that confirms or cancels it from a link in an email.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredSubscription {
+    pub subscription: AlertSubscription,
+    pub token: String,
+}
+
+impl StoredSubscription {
+    pub fn id(&self) -> i64 {
+        self.subscription
+            .id
+            .expect("stored subscriptions have an id")
+    }
+}
+
+/// Alert subscriptions persisted in SQLite. Ids and tokens are random so they cannot be guessed.
+#[derive(Debug)]
+pub struct SubscriptionStore {
+    connection: Mutex<Connection>,
+}
+
+impl SubscriptionStore {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<SubscriptionStore, StoreError> {
+        SubscriptionStore::new(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<SubscriptionStore, StoreError> {
+        SubscriptionStore::new(Connection::open_in_memory()?)
+    }
+
+    fn new(mut connection: Connection) -> Result<SubscriptionStore, StoreError> {
+        migrate(&mut connection)?;
+        Ok(SubscriptionStore {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    /// Stores the subscription with a new id and token. Fails with `StoreError::Conflict` if the
+    /// recipient is subscribed already, only the owner of that subscription knows its id.
+    ///
+    /// Unconfirmed subscriptions are not alerted until `confirm` is called with their token, they
+    /// are replaced by later subscriptions of the same recipient.
+    pub fn insert(
+        &self,
+        subscription: &AlertSubscription,
+        confirmed: bool,
+    ) -> Result<StoredSubscription, StoreError> {
+        let connection = self.connection.lock().expect("store mutex poisoned");
+        let id = (rand::random::<u64>() >> 1) as i64;
+        let token = format!("{:032x}", rand::random::<u128>());
+        connection.execute(
+            "DELETE FROM subscriptions WHERE channel_key = ?1 AND confirmed = 0",
+            params![subscription.channel.key()],
+        )?;
+        connection.execute(
+            "INSERT INTO subscriptions
+                (id, channel_key, channel, place_name, location, quiet_hours, last_alert,
+                 confirmed, token)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, NULL, ?7, ?8)",
+            params![
+                id,
+                subscription.channel.key(),
+                serde_json::to_string(&subscription.channel)?,
+                subscription.place_name,
+                serde_json::to_string(&subscription.location)?,
+                to_json(&subscription.quiet_hours)?,
+                confirmed,
+                token,
+            ],
+        )?;
+        Ok(StoredSubscription {
+            subscription: AlertSubscription {
+                id: Some(id),
+                last_alert: None,
+                ..subscription.clone()
+            },
+            token,
+        })
+    }
+
+    /// Starts alerting the subscription with this token, returns `false` if there is none.
+    pub fn confirm(&self, token: &str) -> Result<bool, StoreError> {
+        let connection = self.connection.lock().expect("store mutex poisoned");
+        let confirmed = connection.execute(
+            "UPDATE subscriptions SET confirmed = 1 WHERE token = ?1",
+            params![token],
+        )?;
+        Ok(confirmed > 0)
+    }
+
+    pub fn get(&self, id: i64) -> Result<Option<AlertSubscription>, StoreError> {
+        let connection = self.connection.lock().expect("store mutex poisoned");
+        let row = connection
+            .query_row(
+                &format!("SELECT {} FROM subscriptions WHERE id = ?1", COLUMNS),
+                params![id],
+                to_columns,
+            )
+            .optional()?;
+        Ok(row
+            .map(from_columns)
+            .transpose()?
+            .map(|stored| stored.subscription))
+    }
+
+    /// Replaces the settings of the subscription, returns `false` if there is none with this id.
+    /// The announced phases are forgotten if the location or thresholds change, they don't
+    /// describe the new forecast.
+    pub fn update(&self, id: i64, subscription: &AlertSubscription) -> Result<bool, StoreError> {
+        let connection = self.connection.lock().expect("store mutex poisoned");
+        let updated = connection.execute(
+            "UPDATE subscriptions SET
+                channel_key = ?2, channel = ?3, place_name = ?4, location = ?5, quiet_hours = ?6,
+                last_alert = CASE WHEN location = ?5 THEN last_alert ELSE NULL END
+             WHERE id = ?1",
+            params![
+                id,
+                subscription.channel.key(),
+                serde_json::to_string(&subscription.channel)?,
+                subscription.place_name,
+                serde_json::to_string(&subscription.location)?,
+                to_json(&subscription.quiet_hours)?,
+            ],
+        )?;
+        Ok(updated > 0)
+    }
+
+    pub fn delete(&self, id: i64) -> Result<bool, StoreError> {
+        let connection = self.connection.lock().expect("store mutex poisoned");
+        let deleted = connection.execute("DELETE FROM subscriptions WHERE id = ?1", params![id])?;
+        Ok(deleted > 0)
+    }
+
+    /// Removes the subscription with this token, returns `false` if there is none.
+    pub fn delete_token(&self, token: &str) -> Result<bool, StoreError> {
+        let connection = self.connection.lock().expect("store mutex poisoned");
+        let deleted =
+            connection.execute("DELETE FROM subscriptions WHERE token = ?1", params![token])?;
+        Ok(deleted > 0)
+    }
+
+    /// Removes the subscription of a recipient, see `AlertChannel::key`.
+    pub fn delete_recipient(&self, channel_key: &str) -> Result<bool, StoreError> {
+        let connection = self.connection.lock().expect("store mutex poisoned");
+        let deleted = connection.execute(
+            "DELETE FROM subscriptions WHERE channel_key = ?1",
+            params![channel_key],
+        )?;
+        Ok(deleted > 0)
+    }
+
+    pub fn set_last_alert(&self, id: i64, last_alert: &AlertState) -> Result<(), StoreError> {
+        let connection = self.connection.lock().expect("store mutex poisoned");
+        connection.execute(
+            "UPDATE subscriptions SET last_alert = ?2 WHERE id = ?1",
+            params![id, serde_json::to_string(last_alert)?],
+        )?;
+        Ok(())
+    }
+
+    /// All confirmed subscriptions.
+    pub fn all(&self) -> Result<Vec<StoredSubscription>, StoreError> {
+        let connection = self.connection.lock().expect("store mutex poisoned");
+        let mut statement = connection.prepare(&format!(
+            "SELECT {} FROM subscriptions WHERE confirmed = 1 ORDER BY id",
+            COLUMNS
+        ))?;
+        let rows = statement
+            .query_map(params![], to_columns)?
+            .collect::<Result<Vec<Columns>, rusqlite::Error>>()?;
+        rows.into_iter().map(from_columns).collect()
+    }
+}
+
+fn migrate(connection: &mut Connection) -> Result<(), StoreError> {
+    let version: usize = connection
+        .query_row("PRAGMA user_version", params![], |row| row.get::<_, i64>(0))?
+        as usize;
+
+    for (i, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        info!("Migrating subscription store to version {}", i + 1);
+        let transaction = connection.transaction()?;
+        transaction.execute_batch(migration)?;
+        transaction.execute_batch(&format!("PRAGMA user_version = {}", i + 1))?;
+        transaction.commit()?;
+    }
+
+    Ok(())
+}
+
+type Columns = (
+    i64,
+    String,
+    String,
+    String,
+    Option<String>,
+    Option<String>,
+    String,
+);
+
+fn to_columns(row: &Row) -> Result<Columns, rusqlite::Error> {
+    Ok((
+        row.get(0)?,
+        row.get(1)?,
+        row.get(2)?,
+        row.get(3)?,
+        row.get(4)?,
+        row.get(5)?,
+        row.get(6)?,
+    ))
+}
+
+fn from_columns(
+    (id, channel, place_name, location, quiet_hours, last_alert, token): Columns,
+) -> Result<StoredSubscription, StoreError> {
+    let subscription = AlertSubscription {
+        id: Some(id),
+        channel: serde_json::from_str(&channel)?,
+        place_name,
+        location: serde_json::from_str(&location)?,
+        quiet_hours: from_json(quiet_hours)?,
+        last_alert: from_json(last_alert)?,
+    };
+    Ok(StoredSubscription {
+        subscription,
+        token,
+    })
+}
+
+fn to_json<T: serde::Serialize>(value: &Option<T>) -> Result<Option<String>, StoreError> {
+    Ok(value.as_ref().map(serde_json::to_string).transpose()?)
+}
+
+fn from_json<T: serde::de::DeserializeOwned>(
+    value: Option<String>,
+) -> Result<Option<T>, StoreError> {
+    Ok(value.as_deref().map(serde_json::from_str).transpose()?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::prelude::*;
+
+    fn subscription(endpoint: &str) -> AlertSubscription {
+        AlertSubscription {
+            id: None,
+            channel: AlertChannel::Push(PushSubscription {
+                endpoint: endpoint.to_owned(),
+                keys: PushKeys {
+                    p256dh: "p256dh".to_owned(),
+                    auth: "auth".to_owned(),
+                },
+            }),
+            place_name: "Garden".to_owned(),
+            location: BatchEntry {
+                lat: 52.52,
+                lon: 13.4,
+                thresholds: Some((5.0, 0.0)),
+                preset: None,
+                level: None,
+                horizon: None,
+            },
+            quiet_hours: Some(QuietHours { start: 22, end: 7 }),
+            last_alert: None,
+        }
+    }
+
+    #[test]
+    fn test_crud() {
+        let store = SubscriptionStore::open_in_memory().unwrap();
+
+        let id = store
+            .insert(&subscription("https://push.example/a"), true)
+            .unwrap()
+            .id();
+        let stored = store.get(id).unwrap().unwrap();
+        assert_eq!(stored.id, Some(id));
+        assert_eq!(stored.place_name, "Garden");
+        assert_eq!(stored.quiet_hours, Some(QuietHours { start: 22, end: 7 }));
+
+        let mut changed = subscription("https://push.example/a");
+        changed.place_name = "Allotment".to_owned();
+        assert!(store.update(id, &changed).unwrap());
+        assert_eq!(store.get(id).unwrap().unwrap().place_name, "Allotment");
+
+        assert!(store.delete(id).unwrap());
+        assert_eq!(store.get(id).unwrap(), None);
+        assert!(!store.delete(id).unwrap());
+        assert!(!store.update(id, &changed).unwrap());
+    }
+
+    #[test]
+    fn test_same_recipient_is_rejected() {
+        let store = SubscriptionStore::open_in_memory().unwrap();
+
+        let id = store
+            .insert(&subscription("https://push.example/a"), true)
+            .unwrap()
+            .id();
+        let mut changed = subscription("https://push.example/a");
+        changed.place_name = "Allotment".to_owned();
+        assert_eq!(store.insert(&changed, true), Err(StoreError::Conflict));
+        assert_eq!(store.get(id).unwrap().unwrap().place_name, "Garden");
+
+        let other = store
+            .insert(&subscription("https://push.example/b"), true)
+            .unwrap()
+            .id();
+        assert_eq!(
+            store.update(other, &subscription("https://push.example/a")),
+            Err(StoreError::Conflict)
+        );
+
+        assert_eq!(store.all().unwrap().len(), 2);
+        assert!(store.delete_recipient("https://push.example/a").unwrap());
+        assert_eq!(store.all().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_confirmation() {
+        let store = SubscriptionStore::open_in_memory().unwrap();
+        let mut email = subscription("");
+        email.channel = AlertChannel::Email {
+            address: "gardener@example.com".to_owned(),
+        };
+
+        let pending = store.insert(&email, false).unwrap();
+        assert_eq!(store.all().unwrap(), vec![]);
+
+        // a pending subscription doesn't block the recipient, only the last one can be confirmed
+        let stored = store.insert(&email, false).unwrap();
+        assert_eq!(store.get(pending.id()).unwrap(), None);
+        assert!(!store.confirm(&pending.token).unwrap());
+        assert!(store.confirm(&stored.token).unwrap());
+        assert_eq!(store.all().unwrap(), vec![stored.clone()]);
+        assert_eq!(store.insert(&email, false), Err(StoreError::Conflict));
+
+        assert!(store.delete_token(&stored.token).unwrap());
+        assert_eq!(store.get(stored.id()).unwrap(), None);
+        assert!(!store.delete_token(&stored.token).unwrap());
+    }
+
+    #[test]
+    fn test_last_alert() {
+        let store = SubscriptionStore::open_in_memory().unwrap();
+        let id = store
+            .insert(&subscription("https://push.example/a"), true)
+            .unwrap()
+            .id();
+
+        let last_alert = AlertState {
+            sent_at: Utc.ymd(2020, 4, 20).and_hms(18, 0, 0),
+            phases: vec![AnnouncedPhase {
+                start: FixedOffset::east(7200).ymd(2020, 4, 21).and_hms(4, 0, 0),
+                end: FixedOffset::east(7200).ymd(2020, 4, 21).and_hms(9, 0, 0),
+                record_type: RecordType::Danger,
+                min_temp: -2.0,
+            }],
+        };
+        store.set_last_alert(id, &last_alert).unwrap();
+
+        assert_eq!(store.get(id).unwrap().unwrap().last_alert, Some(last_alert));
+
+        let mut renamed = subscription("https://push.example/a");
+        renamed.place_name = "Allotment".to_owned();
+        store.update(id, &renamed).unwrap();
+        assert!(store.get(id).unwrap().unwrap().last_alert.is_some());
+
+        let mut moved = renamed.clone();
+        moved.location.thresholds = Some((3.0, -1.0));
+        store.update(id, &moved).unwrap();
+        assert_eq!(store.get(id).unwrap().unwrap().last_alert, None);
+    }
+
+    #[test]
+    fn test_migrations_are_applied_once() {
+        let mut connection = Connection::open_in_memory().unwrap();
+        migrate(&mut connection).unwrap();
+        migrate(&mut connection).unwrap();
+
+        let version: i64 = connection
+            .query_row("PRAGMA user_version", params![], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version as usize, MIGRATIONS.len());
+    }
+
+    #[test]
+    fn test_existing_subscriptions_stay_confirmed() {
+        let mut connection = Connection::open_in_memory().unwrap();
+        connection.execute_batch(MIGRATIONS[0]).unwrap();
+        connection.execute_batch("PRAGMA user_version = 1").unwrap();
+        connection
+            .execute(
+                "INSERT INTO subscriptions (id, channel_key, channel, place_name, location)
+                 VALUES (1, 'gardener@example.com', ?1, 'Garden', ?2)",
+                params![
+                    r#"{"type":"email","address":"gardener@example.com"}"#,
+                    r#"{"lat":52.52,"lon":13.4,"thresholds":[5.0,0.0]}"#
+                ],
+            )
+            .unwrap();
+
+        let store = SubscriptionStore::new(connection).unwrap();
+        let all = store.all().unwrap();
+
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].token.len(), 32);
+    }
+}
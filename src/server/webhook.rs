@@ -0,0 +1,417 @@
+use super::net::{self, public_addresses};
+use frost::common::*;
+use hmac::{Hmac, Mac, NewMac};
+use sha2::Sha256;
+use std::fmt;
+use std::net::SocketAddr;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Header carrying the hex encoded HMAC-SHA256 of the request body, prefixed with `sha256=`.
+pub const SIGNATURE_HEADER: &str = "X-Frost-Signature";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WebhookError {
+    /// the receiver answered 410 Gone and the subscription should be removed
+    Expired,
+    Failed(String),
+}
+
+impl fmt::Display for WebhookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WebhookError::Expired => write!(f, "Webhook is gone"),
+            WebhookError::Failed(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for WebhookError {}
+
+#[derive(Debug, Clone)]
+pub struct WebhookConfig {
+    /// total number of tries per request
+    pub attempts: u32,
+    /// wait before the first retry, doubled for every further retry
+    pub backoff: Duration,
+    /// time all retries of an alert run may take together, they block the alerts of everyone else
+    pub retry_budget: Duration,
+    /// allow plain http and hosts in private networks, only for testing or servers in a LAN
+    pub allow_private: bool,
+    /// timeout of a single request
+    pub timeout: Duration,
+}
+
+/// POSTs signed payloads to webhooks, retrying with exponential backoff.
+#[derive(Debug, Clone)]
+pub struct WebhookSender {
+    client: reqwest::blocking::Client,
+    config: WebhookConfig,
+}
+
+impl WebhookSender {
+    pub fn new(config: WebhookConfig) -> Result<Self, WebhookError> {
+        let client = net::client_builder(config.timeout)
+            .build()
+            .map_err(|e| WebhookError::Failed(e.to_string()))?;
+        Ok(WebhookSender {
+            client,
+            config: WebhookConfig {
+                attempts: config.attempts.max(1),
+                ..config
+            },
+        })
+    }
+
+    pub fn retry_budget(&self) -> Duration {
+        self.config.retry_budget
+    }
+
+    /// Sends the payload, retrying as long as `retry_budget` allows. The time spent on retries
+    /// is subtracted from the budget.
+    pub fn send(
+        &self,
+        url: &str,
+        secret: &str,
+        payload: &WebhookPayload,
+        retry_budget: &mut Duration,
+    ) -> Result<(), WebhookError> {
+        // the host may resolve to a different address than when the subscription was made, and
+        // once more when connecting, so the request has to go to the address checked here
+        let (parsed, address) = self.resolve(url)?;
+        let client = match (parsed.domain(), address) {
+            (Some(domain), Some(address)) => net::client_builder(self.config.timeout)
+                .resolve(domain, address)
+                .build()
+                .map_err(|e| WebhookError::Failed(e.to_string()))?,
+            _ => self.client.clone(),
+        };
+
+        let body = serde_json::to_vec(payload).expect("WebhookPayload can always be serialized");
+        let signature = format!("sha256={}", sign(secret, &body));
+
+        let attempts = self.config.attempts;
+        let mut backoff = self.config.backoff;
+        let mut attempt = 1;
+        loop {
+            let started = Instant::now();
+            let result = post(&client, url, &signature, &body);
+            if attempt > 1 {
+                *retry_budget = retry_budget
+                    .checked_sub(started.elapsed())
+                    .unwrap_or_default();
+            }
+            match result {
+                Err(Retry::Yes(e)) if attempt < attempts && backoff < *retry_budget => {
+                    debug!(
+                        "Webhook attempt {} of {} failed, retrying in {:?}: {}",
+                        attempt, attempts, backoff, e
+                    );
+                    thread::sleep(backoff);
+                    *retry_budget -= backoff;
+                    backoff *= 2;
+                    attempt += 1;
+                }
+                Err(Retry::Yes(e)) | Err(Retry::No(e)) => return Err(e),
+                Ok(()) => return Ok(()),
+            }
+        }
+    }
+
+    /// Checks that the URL can receive webhooks. Unless private hosts are allowed, it has to use
+    /// https and must not resolve to loopback, private or link-local addresses, so subscribers
+    /// can't make the server send requests into its own network.
+    pub fn validate_url(&self, url: &str) -> Result<(), WebhookError> {
+        self.resolve(url).map(|_| ())
+    }
+
+    /// Parses and checks the URL, returns the public address to connect to unless private hosts
+    /// are allowed.
+    fn resolve(&self, url: &str) -> Result<(reqwest::Url, Option<SocketAddr>), WebhookError> {
+        let parsed = reqwest::Url::parse(url).map_err(|e| WebhookError::Failed(e.to_string()))?;
+        match parsed.scheme() {
+            "https" => (),
+            "http" if self.config.allow_private => (),
+            scheme => {
+                return Err(WebhookError::Failed(format!(
+                    "Unsupported URL scheme: {}",
+                    scheme
+                )))
+            }
+        }
+        if self.config.allow_private {
+            return Ok((parsed, None));
+        }
+
+        let addresses = public_addresses(&parsed).map_err(WebhookError::Failed)?;
+        Ok((parsed, addresses.first().copied()))
+    }
+}
+
+fn post(
+    client: &reqwest::blocking::Client,
+    url: &str,
+    signature: &str,
+    body: &[u8],
+) -> Result<(), Retry> {
+    let response = client
+        .post(url)
+        .header("Content-Type", "application/json")
+        .header(SIGNATURE_HEADER, signature)
+        .body(body.to_owned())
+        .send()
+        .map_err(|e| Retry::Yes(WebhookError::Failed(e.to_string())))?;
+
+    let status = response.status().as_u16();
+    match status {
+        200..=299 => Ok(()),
+        410 => Err(Retry::No(WebhookError::Expired)),
+        408 | 429 | 500..=599 => Err(Retry::Yes(failed(status))),
+        _ => Err(Retry::No(failed(status))),
+    }
+}
+
+enum Retry {
+    Yes(WebhookError),
+    No(WebhookError),
+}
+
+fn failed(status: u16) -> WebhookError {
+    WebhookError::Failed(format!("Webhook responded with {}", status))
+}
+
+/// Hex encoded HMAC-SHA256 of the body, keyed with the secret of the subscription.
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_varkey(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::prelude::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    /// Answers one request per status and returns the received requests.
+    fn mock_webhook(statuses: Vec<&'static str>) -> (String, thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hooks/frost", listener.local_addr().unwrap());
+        let handle = thread::spawn(move || {
+            let mut requests = Vec::new();
+            for status in statuses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = Vec::new();
+                let mut buffer = [0; 4096];
+                loop {
+                    let read = stream.read(&mut buffer).unwrap();
+                    request.extend_from_slice(&buffer[..read]);
+                    let text = String::from_utf8_lossy(&request).to_string();
+                    if let Some(end) = text.find("\r\n\r\n") {
+                        let length = text
+                            .lines()
+                            .find_map(|l| {
+                                l.to_lowercase()
+                                    .strip_prefix("content-length: ")
+                                    .map(str::to_owned)
+                            })
+                            .map(|l| l.parse::<usize>().unwrap())
+                            .unwrap_or(0);
+                        if request.len() >= end + 4 + length {
+                            break;
+                        }
+                    }
+                }
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    status
+                );
+                stream.write_all(response.as_bytes()).unwrap();
+                requests.push(String::from_utf8_lossy(&request).to_string());
+            }
+            requests
+        });
+        (url, handle)
+    }
+
+    fn config() -> WebhookConfig {
+        WebhookConfig {
+            attempts: 3,
+            backoff: Duration::from_millis(1),
+            retry_budget: Duration::from_secs(10),
+            // the mock runs on localhost
+            allow_private: true,
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    fn sender() -> WebhookSender {
+        WebhookSender::new(config()).unwrap()
+    }
+
+    fn send(sender: &WebhookSender, url: &str) -> Result<(), WebhookError> {
+        sender.send(url, "secret", &payload(), &mut Duration::from_secs(10))
+    }
+
+    fn payload() -> WebhookPayload {
+        let phase = AnnouncedPhase {
+            start: FixedOffset::east(7200).ymd(2020, 4, 21).and_hms(4, 0, 0),
+            end: FixedOffset::east(7200).ymd(2020, 4, 21).and_hms(9, 0, 0),
+            record_type: RecordType::Danger,
+            min_temp: 6.7,
+        };
+        let trigger = Announcement::AllClear(phase);
+        WebhookPayload {
+            place_name: "Greenhouse".to_owned(),
+            alert: trigger.alert("Greenhouse"),
+            trigger,
+            response: BackendResponse {
+                location: Some("Greenhouse".to_owned()),
+                provider: None,
+                time_zone: None,
+                cold_phases: vec![],
+                heat_phases: vec![],
+                nights: vec![],
+                ice_hazards: vec![],
+            },
+        }
+    }
+
+    #[test]
+    fn test_sign() {
+        // test case 2 of RFC 4231
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_send_signed_payload() {
+        let (url, mock) = mock_webhook(vec!["204 No Content"]);
+
+        send(&sender(), &url).unwrap();
+
+        let requests = mock.join().unwrap();
+        let (headers, body) = requests[0].split_at(requests[0].find("\r\n\r\n").unwrap() + 4);
+        let headers = headers.to_lowercase();
+        assert!(headers.starts_with("post /hooks/frost"));
+        assert!(headers.contains("content-type: application/json"));
+        assert!(headers.contains(&format!(
+            "x-frost-signature: sha256={}",
+            sign("secret", body.as_bytes())
+        )));
+
+        let received: WebhookPayload = serde_json::from_str(body).unwrap();
+        assert_eq!(received, payload());
+        assert!(body.contains(r#""trigger":{"event":"all_clear","phase":{"#));
+    }
+
+    #[test]
+    fn test_retry() {
+        let (url, mock) = mock_webhook(vec![
+            "503 Service Unavailable",
+            "500 Internal Server Error",
+            "200 OK",
+        ]);
+
+        send(&sender(), &url).unwrap();
+
+        assert_eq!(mock.join().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_give_up() {
+        let (url, mock) = mock_webhook(vec!["503 Service Unavailable"; 3]);
+
+        let result = send(&sender(), &url);
+
+        assert_eq!(mock.join().unwrap().len(), 3);
+        assert_eq!(
+            result,
+            Err(WebhookError::Failed(
+                "Webhook responded with 503".to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_no_retry_on_client_error() {
+        let (url, mock) = mock_webhook(vec!["400 Bad Request"]);
+        assert!(send(&sender(), &url).is_err());
+        mock.join().unwrap();
+
+        let (url, mock) = mock_webhook(vec!["410 Gone"]);
+        assert_eq!(send(&sender(), &url), Err(WebhookError::Expired));
+        mock.join().unwrap();
+    }
+
+    #[test]
+    fn test_retry_budget() {
+        let (url, mock) = mock_webhook(vec!["503 Service Unavailable"; 2]);
+        let sender = WebhookSender::new(WebhookConfig {
+            attempts: 10,
+            backoff: Duration::from_millis(20),
+            retry_budget: Duration::from_millis(50),
+            ..config()
+        })
+        .unwrap();
+        let mut budget = sender.retry_budget();
+
+        // waits 20 ms, the next retry would exceed the remaining budget
+        assert!(sender
+            .send(&url, "secret", &payload(), &mut budget)
+            .is_err());
+        assert_eq!(mock.join().unwrap().len(), 2);
+        assert!(budget < Duration::from_millis(40));
+
+        // nothing left for the next webhook of the run
+        let (url, mock) = mock_webhook(vec!["503 Service Unavailable"]);
+        let mut budget = Duration::from_millis(0);
+        assert!(sender
+            .send(&url, "secret", &payload(), &mut budget)
+            .is_err());
+        assert_eq!(mock.join().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_redirects_are_not_followed() {
+        let (url, mock) = mock_webhook(vec![
+            "302 Found\r\nLocation: http://169.254.169.254/latest/meta-data",
+        ]);
+
+        assert_eq!(
+            send(&sender(), &url),
+            Err(WebhookError::Failed(
+                "Webhook responded with 302".to_owned()
+            ))
+        );
+        mock.join().unwrap();
+    }
+
+    #[test]
+    fn test_validate_url() {
+        let sender = WebhookSender::new(WebhookConfig {
+            allow_private: false,
+            ..config()
+        })
+        .unwrap();
+
+        assert!(sender.validate_url("https://93.184.216.34/hook").is_ok());
+        assert!(sender.validate_url("http://93.184.216.34/hook").is_err());
+        assert!(sender.validate_url("ftp://93.184.216.34/hook").is_err());
+        assert!(sender.validate_url("not a url").is_err());
+        assert!(sender.validate_url("https://127.0.0.1/hook").is_err());
+        assert!(sender
+            .validate_url("https://169.254.169.254/latest/meta-data")
+            .is_err());
+
+        assert!(sender().validate_url("http://127.0.0.1:8080/hook").is_ok());
+    }
+}
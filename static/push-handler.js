@@ -0,0 +1,23 @@
+// imported by the generated service worker to show alerts pushed by the server
+
+self.addEventListener('push', event => {
+    if (!event.data) {
+        return;
+    }
+    const alert = event.data.json();
+    event.waitUntil(self.registration.showNotification(alert.title, {
+        body: alert.body,
+        icon: '/icon.png',
+        tag: alert.tag
+    }));
+});
+
+self.addEventListener('notificationclick', event => {
+    event.notification.close();
+    event.waitUntil(clients.matchAll({ type: 'window' }).then(windows => {
+        if (windows.length > 0) {
+            return windows[0].focus();
+        }
+        return clients.openWindow('/');
+    }));
+});
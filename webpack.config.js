@@ -30,6 +30,8 @@ module.exports = {
       // and not allow any straggling "old" SWs to hang around
       clientsClaim: true,
       skipWaiting: true,
+      // shows alerts pushed by the server while the app is closed
+      importScripts: ['push-handler.js'],
     }),
   ]
 };